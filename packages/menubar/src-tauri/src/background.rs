@@ -0,0 +1,106 @@
+//! Background threads and cancellable requests.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// ─── Cancellable Requests ──────────────────────────────────────────────────
+
+/// Cancel flags for long-running commands, keyed by a caller-chosen id.
+static CANCEL_FLAGS: Mutex<Option<HashMap<String, Arc<AtomicBool>>>> = Mutex::new(None);
+
+/// Register `id` and return the flag its worker should poll.
+pub(crate) fn register_cancellable(id: &str) -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    if let Ok(mut flags) = CANCEL_FLAGS.lock() {
+        flags
+            .get_or_insert_with(HashMap::new)
+            .insert(id.to_string(), flag.clone());
+    }
+    flag
+}
+
+pub(crate) fn finish_cancellable(id: &str) {
+    if let Ok(mut flags) = CANCEL_FLAGS.lock() {
+        if let Some(flags) = flags.as_mut() {
+            flags.remove(id);
+        }
+    }
+}
+
+/// Signal the request `id` to stop. Returns whether it was still running.
+pub(crate) fn cancel_request(id: &str) -> bool {
+    let Ok(flags) = CANCEL_FLAGS.lock() else {
+        return false;
+    };
+    match flags.as_ref().and_then(|flags| flags.get(id)) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+// ─── Background Threads ────────────────────────────────────────────────────
+
+/// Set once the app starts shutting down; every background loop checks it.
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+static BACKGROUND_THREADS: Mutex<Vec<std::thread::JoinHandle<()>>> = Mutex::new(Vec::new());
+/// How long quit waits for background threads before abandoning them.
+const THREAD_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub(crate) fn is_shutting_down() -> bool {
+    SHUTDOWN.load(Ordering::SeqCst)
+}
+
+/// Spawn a named thread that is joined (best effort) on quit.
+pub(crate) fn spawn_background(name: &str, f: impl FnOnce() + Send + 'static) {
+    let spawned = std::thread::Builder::new()
+        .name(format!("hexdeck-{name}"))
+        .spawn(f);
+    match spawned {
+        Ok(handle) => {
+            if let Ok(mut threads) = BACKGROUND_THREADS.lock() {
+                threads.retain(|t| !t.is_finished());
+                threads.push(handle);
+            }
+        }
+        Err(e) => menubar_log!("failed to spawn {name} thread: {e}"),
+    }
+}
+
+/// Sleep in short steps so shutdown isn't held up by long intervals.
+/// Returns `false` if shutdown was signalled.
+pub(crate) fn sleep_unless_shutdown(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    loop {
+        if is_shutting_down() {
+            return false;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        std::thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+}
+
+/// Signal all background loops to stop and join them, up to THREAD_JOIN_TIMEOUT.
+pub(crate) fn shutdown_background_threads() {
+    SHUTDOWN.store(true, Ordering::SeqCst);
+    let threads = match BACKGROUND_THREADS.lock() {
+        Ok(mut threads) => std::mem::take(&mut *threads),
+        Err(_) => return,
+    };
+    let deadline = Instant::now() + THREAD_JOIN_TIMEOUT;
+    for thread in threads {
+        while !thread.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        if thread.is_finished() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Opening the dashboard.
+
+use serde::Serialize;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::background::{
+    cancel_request, finish_cancellable, is_shutting_down, register_cancellable, spawn_background,
+};
+use crate::server::{ensure_server_running, is_server_reachable, server_port, SERVER_DEMANDED};
+use crate::server_http::{http_exchange, HTTP_TIMEOUT};
+use crate::settings::{cached_settings, load_settings, save_settings, DashboardOpenMode};
+
+/// One step of `trace_dashboard_connectivity`.
+#[derive(Serialize)]
+struct TraceStep {
+    /// "resolve", "tcp", "tls" or "http".
+    step: &'static str,
+    ok: bool,
+    latency_ms: u64,
+    /// Resolved addresses, peer address or HTTP status, depending on the step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ConnectivityTrace {
+    url: String,
+    steps: Vec<TraceStep>,
+}
+
+/// Time `f` and record it as a step; its `Ok` text becomes the detail.
+fn trace_step<T>(
+    steps: &mut Vec<TraceStep>,
+    step: &'static str,
+    f: impl FnOnce() -> Result<(T, String), String>,
+) -> Option<T> {
+    let started = Instant::now();
+    let result = f();
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok((value, detail)) => {
+            steps.push(TraceStep { step, ok: true, latency_ms, detail: Some(detail), error: None });
+            Some(value)
+        }
+        Err(error) => {
+            steps.push(TraceStep { step, ok: false, latency_ms, detail: None, error: Some(error) });
+            None
+        }
+    }
+}
+
+/// Path and query of an http(s) URL, `/` when it has none.
+fn url_path(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    match rest.find(['/', '?']) {
+        Some(i) if rest[i..].starts_with('/') => rest[i..].split('#').next().unwrap_or("/").to_string(),
+        Some(i) => format!("/{}", rest[i..].split('#').next().unwrap_or_default()),
+        None => "/".to_string(),
+    }
+}
+
+/// Walk the dashboard URL one layer at a time — DNS, TCP, TLS (https only),
+/// HTTP GET — stopping at the first failure, to pinpoint why the dashboard
+/// won't load.
+#[tauri::command]
+pub(crate) async fn trace_dashboard_connectivity() -> Result<ConnectivityTrace, String> {
+    let url = dashboard_url();
+    let (host, port) = parse_http_url(&url)?;
+    let https = url.starts_with("https://");
+    let path = url_path(&url);
+    let host_header = format!("{host}:{port}");
+
+    let trace_url = url.clone();
+    let steps = tauri::async_runtime::spawn_blocking(move || {
+        let mut steps = Vec::new();
+        let Some(addrs) = trace_step(&mut steps, "resolve", || {
+            let addrs: Vec<std::net::SocketAddr> = (host.as_str(), port)
+                .to_socket_addrs()
+                .map_err(|e| e.to_string())?
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} resolved to no addresses"));
+            }
+            let detail = addrs.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ");
+            Ok((addrs, detail))
+        }) else {
+            return steps;
+        };
+        let Some(stream) = trace_step(&mut steps, "tcp", || {
+            let mut last_error = String::new();
+            for addr in &addrs {
+                match TcpStream::connect_timeout(addr, HTTP_TIMEOUT) {
+                    Ok(stream) => {
+                        let _ = stream.set_read_timeout(Some(HTTP_TIMEOUT));
+                        let _ = stream.set_write_timeout(Some(HTTP_TIMEOUT));
+                        return Ok((stream, addr.to_string()));
+                    }
+                    Err(e) => last_error = format!("{addr}: {e}"),
+                }
+            }
+            Err(last_error)
+        }) else {
+            return steps;
+        };
+        let http_detail = |(status, _): (u16, String)| {
+            if (200..400).contains(&status) {
+                Ok(((), format!("HTTP {status}")))
+            } else {
+                Err(format!("HTTP {status}"))
+            }
+        };
+        if https {
+            let Some(mut tls) = trace_step(&mut steps, "tls", || {
+                let connector = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+                let tls = connector.connect(&host, stream).map_err(|e| e.to_string())?;
+                Ok((tls, "handshake complete".to_string()))
+            }) else {
+                return steps;
+            };
+            trace_step(&mut steps, "http", || {
+                http_exchange(&mut tls, "GET", &host_header, &path, None).and_then(http_detail)
+            });
+        } else {
+            let mut stream = stream;
+            trace_step(&mut steps, "http", || {
+                http_exchange(&mut stream, "GET", &host_header, &path, None).and_then(http_detail)
+            });
+        }
+        steps
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(ConnectivityTrace { url: trace_url, steps })
+}
+
+fn dashboard_url() -> String {
+    cached_settings()
+        .dashboard_base_url
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| format!("http://localhost:{}", server_port()))
+}
+
+/// Host and port of an `http(s)://host[:port][/…]` URL, port defaulting by
+/// scheme. Rejects anything else, so a bad setting fails when it's saved.
+pub(crate) fn parse_http_url(url: &str) -> Result<(String, u16), String> {
+    let invalid = || format!("Invalid URL: {url} (expected http:// or https://)");
+    let (rest, default_port) = if let Some(rest) = url.strip_prefix("http://") {
+        (rest, 80)
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        (rest, 443)
+    } else {
+        return Err(invalid());
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let (host, port) = match authority.strip_prefix('[') {
+        // [::1]:8080
+        Some(v6) => {
+            let (host, after) = v6.split_once(']').ok_or_else(invalid)?;
+            (host, after.strip_prefix(':'))
+        }
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+    let port = match port {
+        Some(port) => port.parse::<u16>().ok().filter(|p| *p != 0).ok_or_else(invalid)?,
+        None => default_port,
+    };
+    Ok((host.to_string(), port))
+}
+
+/// Point the dashboard and all server requests at `url`, or back at the
+/// local server with `None`.
+#[tauri::command]
+pub(crate) fn set_dashboard_base_url(url: Option<String>) -> Result<(), String> {
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    if let Some(url) = &url {
+        parse_http_url(url)?;
+    }
+    let mut settings = load_settings();
+    settings.dashboard_base_url = url;
+    save_settings(&settings)
+}
+
+const PREPARE_DASHBOARD_TIMEOUT: Duration = Duration::from_secs(15);
+const PREPARE_DASHBOARD_POLL: Duration = Duration::from_millis(200);
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub(crate) enum PrepareResult {
+    Ready { url: String },
+    Cancelled,
+    TimedOut,
+}
+
+fn wait_for_dashboard(app: &tauri::AppHandle, cancel: &AtomicBool, timeout: Duration) -> PrepareResult {
+    let deadline = Instant::now() + timeout;
+    let mut ensured = false;
+    loop {
+        if cancel.load(Ordering::SeqCst) || is_shutting_down() {
+            return PrepareResult::Cancelled;
+        }
+        if is_server_reachable() {
+            return PrepareResult::Ready { url: dashboard_url() };
+        }
+        if !ensured {
+            // Kick off a spawn once; ensure_server_running itself is rate-limited
+            SERVER_DEMANDED.store(true, Ordering::SeqCst);
+            let app = app.clone();
+            spawn_background("ensure-server", move || ensure_server_running(&app));
+            ensured = true;
+        }
+        if Instant::now() >= deadline {
+            return PrepareResult::TimedOut;
+        }
+        std::thread::sleep(PREPARE_DASHBOARD_POLL);
+    }
+}
+
+/// Make sure the server is up before the dashboard is opened, waiting at
+/// most `timeout_ms`. Cancel with `cancel_prepare_dashboard(request_id)` if
+/// the user gives up.
+#[tauri::command]
+pub(crate) async fn prepare_dashboard(
+    app: tauri::AppHandle,
+    request_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<PrepareResult, String> {
+    let timeout = timeout_ms.map_or(PREPARE_DASHBOARD_TIMEOUT, Duration::from_millis);
+    let cancel = register_cancellable(&request_id);
+    let result = tauri::async_runtime::spawn_blocking(move || wait_for_dashboard(&app, &cancel, timeout))
+        .await
+        .map_err(|e| e.to_string());
+    finish_cancellable(&request_id);
+    result
+}
+
+#[tauri::command]
+pub(crate) fn cancel_prepare_dashboard(request_id: String) -> bool {
+    cancel_request(&request_id)
+}
+
+const WAIT_FOR_SERVER_POLL: Duration = Duration::from_millis(250);
+
+/// Poll until the server is `up` (reachable) or `down` (not), at most
+/// `timeout_ms`. Returns whether the state was reached; a cancel via
+/// `cancel_wait_for_server(request_id)` returns false. Doesn't start or stop
+/// anything itself.
+#[tauri::command]
+pub(crate) async fn wait_for_server(request_id: String, state: String, timeout_ms: u64) -> Result<bool, String> {
+    let want_up = match state.as_str() {
+        "up" => true,
+        "down" => false,
+        other => return Err(format!("Unknown server state: {other} (expected up or down)")),
+    };
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let cancel = register_cancellable(&request_id);
+    let result = tauri::async_runtime::spawn_blocking(move || loop {
+        if cancel.load(Ordering::SeqCst) || is_shutting_down() {
+            return false;
+        }
+        if is_server_reachable() == want_up {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(WAIT_FOR_SERVER_POLL);
+    })
+    .await
+    .map_err(|e| e.to_string());
+    finish_cancellable(&request_id);
+    result
+}
+
+#[tauri::command]
+pub(crate) fn cancel_wait_for_server(request_id: String) -> bool {
+    cancel_request(&request_id)
+}
+
+/// AppleScript that focuses the first Chrome tab showing the dashboard.
+/// Referencing Chrome's terms only compiles where Chrome is installed, so
+/// this is kept apart from the Safari script and only run when it is.
+/// Exits non-zero when no such tab exists (or Chrome isn't running).
+#[cfg(target_os = "macos")]
+const FOCUS_CHROME_TAB_SCRIPT: &str = r#"
+on run argv
+    set dashboardUrl to item 1 of argv
+    if application "Google Chrome" is running then
+        tell application "Google Chrome"
+            repeat with w in windows
+                set i to 0
+                repeat with t in tabs of w
+                    set i to i + 1
+                    if URL of t starts with dashboardUrl then
+                        set active tab index of w to i
+                        set index of w to 1
+                        activate
+                        return
+                    end if
+                end repeat
+            end repeat
+        end tell
+    end if
+    error "no dashboard tab" number 1
+end run
+"#;
+
+/// `FOCUS_CHROME_TAB_SCRIPT` for Safari, which ships with macOS.
+#[cfg(target_os = "macos")]
+const FOCUS_SAFARI_TAB_SCRIPT: &str = r#"
+on run argv
+    set dashboardUrl to item 1 of argv
+    if application "Safari" is running then
+        tell application "Safari"
+            repeat with w in windows
+                repeat with t in tabs of w
+                    if URL of t starts with dashboardUrl then
+                        set current tab of w to t
+                        set index of w to 1
+                        activate
+                        return
+                    end if
+                end repeat
+            end repeat
+        end tell
+    end if
+    error "no dashboard tab" number 1
+end run
+"#;
+
+/// Focus an existing Chrome or Safari tab showing `url`; false when there is
+/// none, so the caller can open a new one.
+#[cfg(target_os = "macos")]
+fn focus_dashboard_tab(url: &str) -> bool {
+    let run = |script: &str| {
+        std::process::Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(url)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    };
+    (macos_app_installed("Google Chrome") && run(FOCUS_CHROME_TAB_SCRIPT)) || run(FOCUS_SAFARI_TAB_SCRIPT)
+}
+
+/// Whether `{name}.app` is in `/Applications` or `~/Applications`.
+#[cfg(target_os = "macos")]
+fn macos_app_installed(name: &str) -> bool {
+    [Some(PathBuf::from("/Applications")), dirs::home_dir().map(|h| h.join("Applications"))]
+        .into_iter()
+        .flatten()
+        .any(|root| root.join(format!("{name}.app")).exists())
+}
+
+/// First installed Chromium-family browser able to open `--app` windows.
+/// This is a fixed preference order, not the system default browser, which
+/// can't be queried portably.
+fn find_chromium_browser() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    let candidates: Vec<PathBuf> = {
+        let apps = [
+            ("Google Chrome", "Google Chrome"),
+            ("Microsoft Edge", "Microsoft Edge"),
+            ("Brave Browser", "Brave Browser"),
+            ("Chromium", "Chromium"),
+        ];
+        let roots = [Some(PathBuf::from("/Applications")), dirs::home_dir().map(|h| h.join("Applications"))];
+        roots
+            .into_iter()
+            .flatten()
+            .flat_map(|root| {
+                apps.iter()
+                    .map(move |(app, exe)| root.join(format!("{app}.app/Contents/MacOS/{exe}")))
+            })
+            .collect()
+    };
+
+    #[cfg(target_os = "windows")]
+    let candidates: Vec<PathBuf> = {
+        let roots = ["ProgramFiles", "ProgramFiles(x86)", "LocalAppData"];
+        let exes = [
+            "Google\\Chrome\\Application\\chrome.exe",
+            "Microsoft\\Edge\\Application\\msedge.exe",
+            "BraveSoftware\\Brave-Browser\\Application\\brave.exe",
+        ];
+        roots
+            .iter()
+            .filter_map(|var| std::env::var_os(var).map(PathBuf::from))
+            .flat_map(|root| exes.iter().map(move |exe| root.join(exe)))
+            .collect()
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let candidates: Vec<PathBuf> = {
+        let names = ["google-chrome", "chromium", "chromium-browser", "microsoft-edge", "brave-browser"];
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        std::env::split_paths(&path)
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .collect()
+    };
+
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// Open the dashboard. In `AppWindow` mode it gets its own chromeless window
+/// when a Chromium browser is installed, else falls back to a browser tab.
+/// With `dashboard_reuse_tab`, an existing tab is focused first where the
+/// browser is scriptable (Safari and Chrome on macOS); other browsers and
+/// platforms always get a new tab.
+pub(crate) fn open_dashboard() {
+    let url = dashboard_url();
+    let settings = cached_settings();
+
+    if settings.dashboard_open_mode == DashboardOpenMode::AppWindow {
+        if let Some(browser) = find_chromium_browser() {
+            let launched = std::process::Command::new(browser)
+                .arg(format!("--app={url}"))
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .is_ok();
+            if launched {
+                return;
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    if settings.dashboard_reuse_tab && focus_dashboard_tab(&url) {
+        return;
+    }
+
+    let _ = open::that_detached(&url);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_http_url_defaults_port_by_scheme() {
+        assert_eq!(parse_http_url("http://example.com"), Ok(("example.com".into(), 80)));
+        assert_eq!(parse_http_url("https://example.com/"), Ok(("example.com".into(), 443)));
+        assert_eq!(parse_http_url("http://localhost:7433"), Ok(("localhost".into(), 7433)));
+    }
+
+    #[test]
+    fn parse_http_url_ignores_path_query_fragment_and_userinfo() {
+        assert_eq!(parse_http_url("https://user:pw@hex.local:8443/a?b#c"), Ok(("hex.local".into(), 8443)));
+        assert_eq!(parse_http_url("http://hex.local?x=1"), Ok(("hex.local".into(), 80)));
+        assert_eq!(parse_http_url("http://hex.local#top"), Ok(("hex.local".into(), 80)));
+    }
+
+    #[test]
+    fn parse_http_url_handles_bracketed_ipv6() {
+        assert_eq!(parse_http_url("http://[::1]:8080/"), Ok(("::1".into(), 8080)));
+        assert_eq!(parse_http_url("https://[fe80::1]"), Ok(("fe80::1".into(), 443)));
+        assert!(parse_http_url("http://[::1").is_err());
+    }
+
+    #[test]
+    fn parse_http_url_rejects_bad_input() {
+        for url in [
+            "ftp://example.com",
+            "example.com",
+            "http://",
+            "http://:8080",
+            "http://host:0",
+            "http://host:65536",
+            "http://host:",
+            "http://bad host",
+        ] {
+            assert!(parse_http_url(url).is_err(), "{url} should be rejected");
+        }
+    }
+}
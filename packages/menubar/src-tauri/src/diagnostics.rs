@@ -0,0 +1,172 @@
+//! Self check and other diagnostics.
+
+use serde::Serialize;
+use std::fs;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+use crate::paths::hexdeck_dir;
+use crate::server::{
+    configured_port, looks_like_hexdeck_server, pids_on_port, process_name, server_binary_path,
+};
+use crate::shortcuts::{popup_shortcut, widget_shortcut};
+
+#[derive(Serialize)]
+pub(crate) struct CheckResult {
+    pub(crate) name: &'static str,
+    passed: bool,
+    hint: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str) -> Self {
+        CheckResult { name, passed: true, hint: None }
+    }
+
+    fn fail(name: &'static str, hint: impl Into<String>) -> Self {
+        CheckResult { name, passed: false, hint: Some(hint.into()) }
+    }
+}
+
+fn check_config_dir_writable() -> CheckResult {
+    let Some(dir) = hexdeck_dir() else {
+        return CheckResult::fail("config_dir_writable", "Home directory is unavailable");
+    };
+    let probe = dir.join(".write-test");
+    let result = fs::create_dir_all(&dir).and_then(|_| fs::write(&probe, b"ok"));
+    let _ = fs::remove_file(&probe);
+    match result {
+        Ok(()) => CheckResult::pass("config_dir_writable"),
+        Err(e) => CheckResult::fail(
+            "config_dir_writable",
+            format!("Cannot write to {}: {e}", dir.display()),
+        ),
+    }
+}
+
+fn check_server_binary(app: &tauri::AppHandle) -> CheckResult {
+    match server_binary_path(app) {
+        Ok(binary) if binary.exists() => CheckResult::pass("server_binary"),
+        Ok(binary) => CheckResult::fail(
+            "server_binary",
+            format!("Server binary not found at {}; reinstall Hexdeck", binary.display()),
+        ),
+        Err(e) => CheckResult::fail("server_binary", e),
+    }
+}
+
+fn check_server_port() -> CheckResult {
+    let port = configured_port();
+    let owners: Vec<String> = pids_on_port(port)
+        .into_iter()
+        .filter_map(process_name)
+        .collect();
+    if owners.is_empty() || owners.iter().any(|name| looks_like_hexdeck_server(name)) {
+        return CheckResult::pass("server_port");
+    }
+    CheckResult::fail(
+        "server_port",
+        format!("Port {port} is in use by {}; stop it and retry", owners.join(", ")),
+    )
+}
+
+fn check_shortcuts(app: &tauri::AppHandle) -> CheckResult {
+    let global_shortcut = app.global_shortcut();
+    if [popup_shortcut(), widget_shortcut()]
+        .into_iter()
+        .all(|shortcut| global_shortcut.is_registered(shortcut))
+    {
+        return CheckResult::pass("shortcuts_registered");
+    }
+    CheckResult::fail(
+        "shortcuts_registered",
+        "Global shortcuts are not registered; another app may own them or input permissions are missing",
+    )
+}
+
+/// Validate the environment the menubar depends on, one entry per check.
+/// The port and process checks block, so this runs off the main thread.
+#[tauri::command]
+pub(crate) async fn run_self_check(app: tauri::AppHandle) -> Result<Vec<CheckResult>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let home = if hexdeck_dir().is_some() {
+            CheckResult::pass("home_dir")
+        } else {
+            CheckResult::fail("home_dir", "Cannot resolve home directory; set $HOME or $HEXDECK_HOME")
+        };
+        vec![
+            home,
+            check_config_dir_writable(),
+            check_server_binary(&app),
+            check_server_port(),
+            check_shortcuts(&app),
+        ]
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// This process's footprint, for spotting leaks. Fields the platform can't
+/// report are `None`.
+#[derive(Serialize)]
+pub(crate) struct ResourceUsage {
+    rss_bytes: Option<u64>,
+    thread_count: Option<u64>,
+    /// Webview windows that exist, shown or not.
+    window_count: usize,
+    visible_window_count: usize,
+}
+
+/// Resident set size and thread count from procfs.
+#[cfg(target_os = "linux")]
+fn process_usage() -> (Option<u64>, Option<u64>) {
+    let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+    (field("VmRSS:").map(|kb| kb * 1024), field("Threads:"))
+}
+
+/// Resident set size and thread count via `ps`, as for `process_name`.
+#[cfg(target_os = "macos")]
+fn process_usage() -> (Option<u64>, Option<u64>) {
+    let pid = std::process::id().to_string();
+    let rss = std::process::Command::new("ps")
+        .args(["-p", &pid, "-o", "rss="])
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024);
+    // -M prints a header plus one line per thread
+    let threads = std::process::Command::new("ps")
+        .args(["-M", "-p", &pid])
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().count().saturating_sub(1) as u64)
+        .filter(|count| *count > 0);
+    (rss, threads)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn process_usage() -> (Option<u64>, Option<u64>) {
+    (None, None)
+}
+
+#[tauri::command]
+pub(crate) fn get_resource_usage(app: tauri::AppHandle) -> ResourceUsage {
+    let (rss_bytes, thread_count) = process_usage();
+    let windows = app.webview_windows();
+    ResourceUsage {
+        rss_bytes,
+        thread_count,
+        window_count: windows.len(),
+        visible_window_count: windows
+            .values()
+            .filter(|w| w.is_visible().unwrap_or(false))
+            .count(),
+    }
+}
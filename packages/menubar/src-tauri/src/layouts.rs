@@ -0,0 +1,296 @@
+//! Named window layouts.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{Emitter, Manager};
+
+use crate::main_window::position_window_at_tray;
+use crate::paths::hexdeck_dir;
+use crate::profiles::is_valid_name;
+use crate::settings::{load_settings, save_settings};
+use crate::widget::set_widget_shown;
+use crate::widget_position::{
+    discard_pending_position, load_widget_position, push_position_history, widget_pinned_monitor,
+    widget_work_area, write_widget_position, WidgetPosition,
+};
+
+/// Geometry of one window in physical pixels.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub(crate) struct WindowLayout {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) visible: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Layout {
+    widget: Option<WindowLayout>,
+    main: Option<WindowLayout>,
+}
+
+fn layouts_dir() -> Option<PathBuf> {
+    hexdeck_dir().map(|d| d.join("layouts"))
+}
+
+fn layout_file(name: &str) -> Result<PathBuf, String> {
+    if !is_valid_name(name) {
+        return Err(format!("Invalid layout name: {name}"));
+    }
+    let dir = layouts_dir().ok_or("Cannot resolve home directory")?;
+    Ok(dir.join(format!("{name}.json")))
+}
+
+fn capture_window_layout(app: &tauri::AppHandle, label: &str) -> Option<WindowLayout> {
+    let window = app.get_webview_window(label)?;
+    let position = window.outer_position().ok()?;
+    let size = window.outer_size().ok()?;
+    Some(WindowLayout {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        visible: window.is_visible().unwrap_or(false),
+    })
+}
+
+/// Clamp `layout` so the window lies within the work area of the monitor
+/// containing its top-left corner, or the primary monitor if the saved
+/// position is now off-screen (e.g. a disconnected display).
+pub(crate) fn clamp_to_monitor(window: &tauri::WebviewWindow, layout: WindowLayout) -> WindowLayout {
+    let monitors = window.available_monitors().unwrap_or_default();
+    let containing = monitors.into_iter().find(|m| {
+        let area = m.work_area();
+        layout.x >= area.position.x
+            && layout.y >= area.position.y
+            && layout.x < area.position.x + area.size.width as i32
+            && layout.y < area.position.y + area.size.height as i32
+    });
+    let Some(monitor) = containing.or_else(|| window.primary_monitor().ok().flatten()) else {
+        return layout;
+    };
+    let area = monitor.work_area();
+    clamp_to_area(layout, area.position, area.size)
+}
+
+/// Shrink and move `layout` so it fits inside the given area.
+pub(crate) fn clamp_to_area(
+    layout: WindowLayout,
+    origin: tauri::PhysicalPosition<i32>,
+    size: tauri::PhysicalSize<u32>,
+) -> WindowLayout {
+    let width = layout.width.min(size.width);
+    let height = layout.height.min(size.height);
+    let max_x = origin.x + (size.width - width) as i32;
+    let max_y = origin.y + (size.height - height) as i32;
+    WindowLayout {
+        x: layout.x.clamp(origin.x, max_x),
+        y: layout.y.clamp(origin.y, max_y),
+        width,
+        height,
+        visible: layout.visible,
+    }
+}
+
+fn apply_window_layout(window: &tauri::WebviewWindow, layout: WindowLayout) -> Result<(), String> {
+    let layout = clamp_to_monitor(window, layout);
+    window
+        .set_size(tauri::PhysicalSize::new(layout.width, layout.height))
+        .map_err(|e| e.to_string())?;
+    window
+        .set_position(tauri::PhysicalPosition::new(layout.x, layout.y))
+        .map_err(|e| e.to_string())
+}
+
+/// Where `reset_positions` put each window, in physical pixels.
+#[derive(Clone, Serialize)]
+pub(crate) struct PositionsReset {
+    pub(crate) widget: Option<WidgetPosition>,
+    main: Option<WidgetPosition>,
+}
+
+/// Center the widget on its pinned (else primary) monitor's work area.
+fn recenter_widget(widget: &tauri::WebviewWindow) -> Option<WidgetPosition> {
+    let monitor = widget_pinned_monitor(widget).or_else(|| widget.primary_monitor().ok().flatten())?;
+    let size = widget.outer_size().ok()?;
+    let area = widget_work_area(widget, &monitor);
+    let centered = WindowLayout {
+        x: area.position.x + (area.size.width as i32 - size.width as i32) / 2,
+        y: area.position.y + (area.size.height as i32 - size.height as i32) / 2,
+        width: size.width,
+        height: size.height,
+        visible: true,
+    };
+    let layout = clamp_to_area(centered, area.position, area.size);
+    widget
+        .set_position(tauri::PhysicalPosition::new(layout.x, layout.y))
+        .ok()?;
+    Some(WidgetPosition { x: layout.x as f64, y: layout.y as f64 })
+}
+
+/// Dock the popup under the tray icon, or center it without a tray.
+pub(crate) fn redock_main(app: &tauri::AppHandle, main: &tauri::WebviewWindow) -> Option<WidgetPosition> {
+    let docked = app
+        .tray_by_id("main-tray")
+        .and_then(|tray| position_window_at_tray(main, &tray));
+    let position = match docked {
+        Some(position) => position,
+        None => {
+            main.center().ok()?;
+            main.outer_position().ok()?
+        }
+    };
+    let size = main.outer_size().ok()?;
+    let layout = clamp_to_monitor(
+        main,
+        WindowLayout {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            visible: true,
+        },
+    );
+    main.set_position(tauri::PhysicalPosition::new(layout.x, layout.y)).ok()?;
+    Some(WidgetPosition { x: layout.x as f64, y: layout.y as f64 })
+}
+
+/// Recovery for off-screen windows: forget saved positions and put the
+/// widget back in the middle of the screen and the popup under the tray.
+/// Settings are left alone. Emits `positions-reset` with the new positions.
+#[tauri::command]
+pub(crate) fn reset_positions(app: tauri::AppHandle) -> Result<PositionsReset, String> {
+    discard_pending_position();
+    // Keep the old spot undoable, in case the reset was the mistake
+    if let Some(previous) = load_widget_position() {
+        push_position_history(previous)?;
+    }
+    let dir = hexdeck_dir().ok_or("Cannot resolve home directory")?;
+    for name in ["widget-position.json", "main-position.json"] {
+        match fs::remove_file(dir.join(name)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Cannot remove {name}: {e}")),
+        }
+    }
+
+    let reset = PositionsReset {
+        widget: app.get_webview_window("widget").and_then(|w| recenter_widget(&w)),
+        main: app.get_webview_window("main").and_then(|w| redock_main(&app, &w)),
+    };
+    let _ = app.emit("positions-reset", reset.clone());
+    Ok(reset)
+}
+
+/// Snapshot the widget and popup geometry and visibility under `name`.
+#[tauri::command]
+pub(crate) fn save_layout(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let path = layout_file(&name)?;
+    let layout = Layout {
+        widget: capture_window_layout(&app, "widget"),
+        main: capture_window_layout(&app, "main"),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&layout).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub(crate) fn restore_layout(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let path = layout_file(&name)?;
+    let data = fs::read_to_string(&path).map_err(|_| format!("Unknown layout: {name}"))?;
+    let layout: Layout = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+
+    if let (Some(saved), Some(widget)) = (layout.widget, app.get_webview_window("widget")) {
+        apply_window_layout(&widget, saved)?;
+        // An explicit placement replaces any anchor, like a drag would
+        let mut settings = load_settings();
+        let had_anchor = settings.widget_anchor.take().is_some();
+        if settings.widget_position_pct.take().is_some() || had_anchor {
+            save_settings(&settings)?;
+        }
+        if let Ok(position) = widget.outer_position() {
+            discard_pending_position();
+            write_widget_position(&WidgetPosition {
+                x: position.x as f64,
+                y: position.y as f64,
+            })?;
+        }
+        set_widget_shown(&app, saved.visible);
+    }
+
+    if let (Some(saved), Some(main)) = (layout.main, app.get_webview_window("main")) {
+        apply_window_layout(&main, saved)?;
+        if saved.visible {
+            let _ = main.show();
+            let _ = main.set_focus();
+        } else {
+            let _ = main.hide();
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn list_layouts() -> Vec<String> {
+    let Some(entries) = layouts_dir().and_then(|d| fs::read_dir(d).ok()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            path.file_stem()?.to_str().map(str::to_string)
+        })
+        .filter(|name| is_valid_name(name))
+        .collect();
+    names.sort();
+    names
+}
+
+#[tauri::command]
+pub(crate) fn delete_layout(name: String) -> Result<(), String> {
+    let path = layout_file(&name)?;
+    fs::remove_file(&path).map_err(|_| format!("Unknown layout: {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(x: i32, y: i32, width: u32, height: u32) -> WindowLayout {
+        WindowLayout { x, y, width, height, visible: true }
+    }
+
+    fn clamped(layout: WindowLayout) -> (i32, i32, u32, u32) {
+        let area = clamp_to_area(
+            layout,
+            tauri::PhysicalPosition::new(100, 50),
+            tauri::PhysicalSize::new(800, 600),
+        );
+        (area.x, area.y, area.width, area.height)
+    }
+
+    #[test]
+    fn clamp_to_area_keeps_layouts_that_fit() {
+        assert_eq!(clamped(layout(200, 100, 300, 200)), (200, 100, 300, 200));
+    }
+
+    #[test]
+    fn clamp_to_area_moves_layouts_back_inside() {
+        assert_eq!(clamped(layout(0, 0, 300, 200)), (100, 50, 300, 200));
+        assert_eq!(clamped(layout(850, 600, 300, 200)), (600, 450, 300, 200));
+    }
+
+    #[test]
+    fn clamp_to_area_shrinks_oversized_layouts() {
+        assert_eq!(clamped(layout(-500, 900, 1200, 1000)), (100, 50, 800, 600));
+    }
+}
@@ -0,0 +1,311 @@
+//! The popup (main) window.
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::background::{sleep_unless_shutdown, spawn_background};
+use crate::layouts::{clamp_to_monitor, redock_main, WindowLayout};
+use crate::paths::{main_position_file, write_atomic};
+use crate::server::demand_server;
+use crate::settings::{cached_settings, load_settings, save_settings, HideMode, MainOpenPlacement};
+use crate::tray::{tray_rect_physical, TrayRect};
+use crate::widget::{apply_window_stacking, set_widget_shown, SHOW_WIDGET};
+use crate::widget_position::WidgetPosition;
+
+pub(crate) const MAIN_MIN_WIDTH: f64 = 280.0;
+pub(crate) const MAIN_MIN_HEIGHT: f64 = 320.0;
+
+/// Resize the popup to the persisted size, if one was saved.
+pub(crate) fn apply_main_size(window: &tauri::WebviewWindow) {
+    let settings = cached_settings();
+    if let (Some(width), Some(height)) = (settings.main_width, settings.main_height) {
+        let _ = window.set_size(tauri::LogicalSize::new(
+            width.max(MAIN_MIN_WIDTH),
+            height.max(MAIN_MIN_HEIGHT),
+        ));
+    }
+}
+
+#[tauri::command]
+pub(crate) fn save_main_size(app: tauri::AppHandle, width: f64, height: f64) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.main_width = Some(width.max(MAIN_MIN_WIDTH));
+    settings.main_height = Some(height.max(MAIN_MIN_HEIGHT));
+    save_settings(&settings)?;
+    if let Some(window) = app.get_webview_window("main") {
+        apply_main_size(&window);
+    }
+    Ok(())
+}
+
+/// Set while the popup is shown by `show_main_on_server_ready`; focus loss
+/// doesn't hide it until the user toggles it themselves.
+static MAIN_PINNED: AtomicBool = AtomicBool::new(false);
+
+/// Show the popup unattended once the server answers, positioned at the tray
+/// when there is one.
+pub(crate) fn show_main_for_server_ready(app: &tauri::AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    MAIN_PINNED.store(true, Ordering::SeqCst);
+    show_main_at_tray(app, &window);
+}
+
+/// Size the popup, dock it under the tray icon (or center it without one)
+/// and bring it to the front.
+pub(crate) fn show_main_at_tray(app: &tauri::AppHandle, window: &tauri::WebviewWindow) {
+    apply_main_size(window);
+    match app.tray_by_id("main-tray") {
+        Some(tray) => {
+            position_window_at_tray(window, &tray);
+        }
+        None => {
+            let _ = window.center();
+        }
+    }
+    let _ = window.show();
+    let _ = window.set_focus();
+    apply_window_stacking(app);
+}
+
+#[tauri::command]
+pub(crate) fn set_main_decorations(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let window = app.get_webview_window("main").ok_or("Main window not found")?;
+    window.set_decorations(enabled).map_err(|e| e.to_string())?;
+    let mut settings = load_settings();
+    settings.main_decorations = enabled;
+    save_settings(&settings)
+}
+
+/// Focus-change policy for the popup. A focus loss right after a tray click
+/// is always ignored (the click itself steals focus), whatever the mode.
+pub(crate) fn handle_main_focus_change(window: &tauri::WebviewWindow, focused: bool, tray_click_guard: &AtomicBool) {
+    if focused {
+        // Window just received focus — clear the guard
+        tray_click_guard.store(false, Ordering::SeqCst);
+        return;
+    }
+    if tray_click_guard.swap(false, Ordering::SeqCst) {
+        return; // suppress this one focus-loss
+    }
+    if MAIN_PINNED.load(Ordering::SeqCst) {
+        return;
+    }
+    let settings = cached_settings();
+    if settings.main_decorations {
+        return;
+    }
+    match settings.main_hide_mode {
+        HideMode::Never => {}
+        HideMode::Immediate => {
+            hide_main_window(window);
+        }
+        HideMode::AfterDelay => {
+            let window = window.clone();
+            let delay = Duration::from_millis(settings.main_hide_delay_ms);
+            spawn_background("main-hide-delay", move || {
+                // Refocusing within the delay cancels the hide
+                if sleep_unless_shutdown(delay) && !window.is_focused().unwrap_or(false) {
+                    hide_main_window(&window);
+                }
+            });
+        }
+    }
+}
+
+/// How far above its resting place the popup starts sliding, in logical pixels.
+const POPUP_SLIDE_DISTANCE: f64 = 12.0;
+const POPUP_SLIDE_DURATION: Duration = Duration::from_millis(140);
+const POPUP_SLIDE_STEPS: u32 = 8;
+
+/// Whether the user has asked the OS to minimize motion.
+#[cfg(target_os = "macos")]
+fn prefers_reduced_motion() -> bool {
+    use objc2::{class, msg_send, runtime::AnyObject};
+    unsafe {
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        if workspace.is_null() {
+            return false;
+        }
+        msg_send![&*workspace, accessibilityDisplayShouldReduceMotion]
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn prefers_reduced_motion() -> bool {
+    false
+}
+
+/// Ease the window from where it is to `target` on a background thread. Only
+/// the position changes, so focus and the tray click guard are unaffected;
+/// hiding the window mid-slide stops it.
+fn slide_window_to(window: &tauri::WebviewWindow, target: tauri::PhysicalPosition<i32>) {
+    let Ok(start) = window.outer_position() else {
+        return;
+    };
+    let window = window.clone();
+    spawn_background("popup-slide", move || {
+        let step = POPUP_SLIDE_DURATION / POPUP_SLIDE_STEPS;
+        for i in 1..=POPUP_SLIDE_STEPS {
+            if !sleep_unless_shutdown(step) || !window.is_visible().unwrap_or(false) {
+                return;
+            }
+            // Ease-out: fast start, gentle landing
+            let t = i as f64 / POPUP_SLIDE_STEPS as f64;
+            let eased = 1.0 - (1.0 - t).powi(3);
+            let y = start.y as f64 + (target.y - start.y) as f64 * eased;
+            let _ = window.set_position(tauri::PhysicalPosition::new(target.x, y.round() as i32));
+        }
+    });
+}
+
+/// Hide the popup, remembering where it was for `RestoreLast`.
+pub(crate) fn hide_main_window(window: &tauri::WebviewWindow) {
+    if let (Ok(position), Some(path)) = (window.outer_position(), main_position_file()) {
+        let position = WidgetPosition { x: position.x as f64, y: position.y as f64 };
+        if let Ok(json) = serde_json::to_string(&position) {
+            let _ = write_atomic(&path, json.as_bytes());
+        }
+    }
+    let _ = window.hide();
+}
+
+fn load_main_position() -> Option<WidgetPosition> {
+    let data = fs::read_to_string(main_position_file()?).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+/// Position the (hidden, already resized) popup per `main_open_placement`.
+/// Returns the docked position when it was docked under the tray, which is
+/// the only placement the open animation slides into.
+fn place_main_for_open(
+    app: &tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    tray: Option<&tauri::tray::TrayIcon>,
+) -> Option<tauri::PhysicalPosition<i32>> {
+    match cached_settings().main_open_placement {
+        MainOpenPlacement::RestoreLast => {
+            let restored = load_main_position().zip(window.outer_size().ok()).map(|(saved, size)| {
+                // The monitor it was on may be gone; keep it reachable
+                clamp_to_monitor(
+                    window,
+                    WindowLayout {
+                        x: saved.x as i32,
+                        y: saved.y as i32,
+                        width: size.width,
+                        height: size.height,
+                        visible: true,
+                    },
+                )
+            });
+            if let Some(layout) = restored {
+                let _ = window.set_position(tauri::PhysicalPosition::new(layout.x, layout.y));
+                return None;
+            }
+            // Never hidden yet: fall back to docking
+        }
+        MainOpenPlacement::Center => {
+            let _ = window.center();
+            return None;
+        }
+        MainOpenPlacement::DockUnderTray => {}
+    }
+    match tray {
+        Some(tray) => position_window_at_tray(window, tray),
+        None => {
+            let _ = redock_main(app, window);
+            None
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn set_main_open_placement(placement: MainOpenPlacement) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.main_open_placement = placement;
+    save_settings(&settings)
+}
+
+pub(crate) fn toggle_main_window_from_tray(
+    app: &tauri::AppHandle,
+    tray: &tauri::tray::TrayIcon,
+    tray_click_guard: &AtomicBool,
+) {
+    if let Some(window) = app.get_webview_window("main") {
+        MAIN_PINNED.store(false, Ordering::SeqCst);
+        if window.is_visible().unwrap_or(false) {
+            hide_main_window(&window);
+        } else {
+            tray_click_guard.store(true, Ordering::SeqCst);
+            demand_server(app);
+            // Resize first: position_window_at_tray centers using outer_size,
+            // which is queued behind set_size and so sees the new size.
+            apply_main_size(&window);
+            let target = place_main_for_open(app, &window, Some(tray));
+            if cached_settings().left_click_shows_widget && !SHOW_WIDGET.load(Ordering::SeqCst) {
+                set_widget_shown(app, true);
+            }
+            let animate = target.filter(|_| cached_settings().animations && !prefers_reduced_motion());
+            if let Some(target) = animate {
+                // Start the slide above the target before the window appears
+                let offset = (POPUP_SLIDE_DISTANCE * window.scale_factor().unwrap_or(1.0)) as i32;
+                let _ = window.set_position(tauri::PhysicalPosition::new(target.x, target.y - offset));
+            }
+            let _ = window.show();
+            let _ = window.set_focus();
+            apply_window_stacking(app);
+            if let Some(target) = animate {
+                slide_window_to(&window, target);
+            }
+        }
+    }
+}
+
+pub(crate) fn toggle_main_window_from_shortcut(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        MAIN_PINNED.store(false, Ordering::SeqCst);
+        if window.is_visible().unwrap_or(false) {
+            hide_main_window(&window);
+        } else {
+            demand_server(app);
+            apply_main_size(&window);
+            let tray = app.tray_by_id("main-tray");
+            place_main_for_open(app, &window, tray.as_ref());
+            let _ = window.show();
+            let _ = window.set_focus();
+            apply_window_stacking(app);
+        }
+    }
+}
+
+/// Move the window under the tray icon; returns where it was put.
+pub(crate) fn position_window_at_tray(
+    window: &tauri::WebviewWindow,
+    tray: &tauri::tray::TrayIcon,
+) -> Option<tauri::PhysicalPosition<i32>> {
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let TrayRect {
+        x: tray_x,
+        y: tray_y,
+        width: tray_w,
+        height: tray_h,
+    } = tray_rect_physical(tray, scale)?;
+
+    let window_size = window.outer_size().ok()?;
+    let window_width = window_size.width as f64;
+
+    // Center window horizontally under the tray icon, then apply the user's nudge
+    let settings = cached_settings();
+    let x = tray_x + (tray_w / 2.0) - (window_width / 2.0) + settings.popup_offset_x;
+    let y = tray_y + tray_h + settings.popup_tray_gap;
+
+    let position = tauri::PhysicalPosition {
+        x: x as i32,
+        y: y as i32,
+    };
+    let _ = window.set_position(tauri::Position::Physical(position));
+    Some(position)
+}
@@ -0,0 +1,65 @@
+//! System notifications.
+
+use serde::Serialize;
+use tauri::Emitter;
+
+use crate::settings::{cached_settings, load_settings, save_settings};
+
+#[derive(Clone, Serialize)]
+struct NotificationFallback {
+    title: String,
+    body: String,
+    reason: String,
+}
+
+fn notifications_permitted(app: &tauri::AppHandle) -> bool {
+    use tauri_plugin_notification::{NotificationExt, PermissionState};
+    matches!(
+        app.notification().permission_state(),
+        Ok(PermissionState::Granted)
+    )
+}
+
+/// Post a system notification. If permission is missing or the plugin fails,
+/// the message is logged and emitted as `notification-fallback` so the widget
+/// can surface it instead of it being lost.
+pub(crate) fn notify(app: &tauri::AppHandle, title: &str, body: &str) -> Result<(), String> {
+    use tauri_plugin_notification::NotificationExt;
+    let result = if notifications_permitted(app) {
+        let builder = app.notification().builder().title(title).body(body);
+        let builder = if cached_settings().notification_sound {
+            builder.sound("default")
+        } else {
+            builder.silent()
+        };
+        builder.show().map_err(|e| e.to_string())
+    } else {
+        Err("notification permission not granted".to_string())
+    };
+    if let Err(reason) = &result {
+        menubar_log!("{title}: {body} (notification failed: {reason})");
+        let _ = app.emit(
+            "notification-fallback",
+            NotificationFallback {
+                title: title.to_string(),
+                body: body.to_string(),
+                reason: reason.clone(),
+            },
+        );
+    }
+    result
+}
+
+#[tauri::command]
+pub(crate) fn set_notification_sound(enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.notification_sound = enabled;
+    save_settings(&settings)
+}
+
+/// Whether system notifications can be shown, so the UI can prompt the user
+/// to grant permission.
+#[tauri::command]
+pub(crate) fn notifications_available(app: tauri::AppHandle) -> bool {
+    notifications_permitted(&app)
+}
@@ -0,0 +1,259 @@
+//! Where Hexdeck keeps its files.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::background::spawn_background;
+use crate::logs::server_log_file;
+use crate::now_secs;
+use crate::profiles::{active_profile, active_profile_file, DEFAULT_PROFILE};
+use crate::server::{ensure_server_running, is_server_wanted, kill_server, reset_spawn_attempts};
+use crate::settings::{cached_settings, load_settings, save_settings};
+
+/// Default base directory: `$HEXDECK_HOME` when set (for containers/CI
+/// without a home directory), otherwise `~/.hexdeck`. Profiles and the
+/// active-profile pointer live here.
+pub(crate) fn bootstrap_dir() -> Option<PathBuf> {
+    if let Some(home) = std::env::var_os("HEXDECK_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(home));
+    }
+    dirs::home_dir().map(|h| h.join(".hexdeck"))
+}
+
+/// Resolved `hexdeck_dir`, since log and PID writes look it up constantly.
+/// Cleared whenever settings are saved (`set_data_dir` goes through
+/// `save_settings`) and on profile switches.
+static HEXDECK_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Directory of the active profile. The default profile is the flat
+/// bootstrap directory, so pre-profile installs keep working unchanged.
+fn profile_dir() -> Option<PathBuf> {
+    let base = bootstrap_dir()?;
+    match active_profile().as_str() {
+        DEFAULT_PROFILE => Some(base),
+        name => Some(base.join("profiles").join(name)),
+    }
+}
+
+/// Base directory for Hexdeck state: the profile's `data_dir` if set,
+/// otherwise the profile directory. The settings file always stays in the
+/// profile directory, since it is what says whether `data_dir` moved the rest.
+pub(crate) fn hexdeck_dir() -> Option<PathBuf> {
+    if let Some(dir) = HEXDECK_DIR.lock().ok().and_then(|dir| dir.clone()) {
+        return Some(dir);
+    }
+    let dir = cached_settings()
+        .data_dir
+        .map(PathBuf::from)
+        .or_else(profile_dir)?;
+    if let Ok(mut cached) = HEXDECK_DIR.lock() {
+        *cached = Some(dir.clone());
+    }
+    Some(dir)
+}
+
+pub(crate) fn invalidate_hexdeck_dir() {
+    if let Ok(mut cached) = HEXDECK_DIR.lock() {
+        *cached = None;
+    }
+}
+
+pub(crate) fn position_file() -> Option<PathBuf> {
+    hexdeck_dir().map(|d| d.join("widget-position.json"))
+}
+
+/// Where the popup was last hidden, for `MainOpenPlacement::RestoreLast`.
+pub(crate) fn main_position_file() -> Option<PathBuf> {
+    hexdeck_dir().map(|d| d.join("main-position.json"))
+}
+
+/// Earlier widget positions, oldest first, for `undo_widget_position`.
+pub(crate) fn position_history_file() -> Option<PathBuf> {
+    hexdeck_dir().map(|d| d.join("widget-position-history.json"))
+}
+
+pub(crate) fn settings_file() -> Option<PathBuf> {
+    profile_dir().map(|d| d.join("menubar-settings.json"))
+}
+
+pub(crate) fn logs_dir() -> Option<PathBuf> {
+    hexdeck_dir().map(|d| d.join("logs"))
+}
+
+/// Where our files live after `$HEXDECK_HOME`, profile and `data_dir`
+/// overrides; `None` when no base directory can be resolved.
+#[derive(Serialize)]
+pub(crate) struct ConfigPaths {
+    settings_file: Option<PathBuf>,
+    position_file: Option<PathBuf>,
+    hexdeck_dir: Option<PathBuf>,
+    logs_dir: Option<PathBuf>,
+    server_log: Option<PathBuf>,
+}
+
+#[tauri::command]
+pub(crate) fn get_config_paths() -> ConfigPaths {
+    ConfigPaths {
+        settings_file: settings_file(),
+        position_file: position_file(),
+        hexdeck_dir: hexdeck_dir(),
+        logs_dir: logs_dir(),
+        server_log: server_log_file(),
+    }
+}
+
+/// Write via a temp file and rename, so a crash or quit mid-write leaves
+/// either the old or the new contents, never a truncated file.
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    {
+        let mut file = fs::File::create(&tmp)?;
+        std::io::Write::write_all(&mut file, data)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+/// Fix the two conditions that make every save fail with an opaque IO error:
+/// the config path being a regular file, and the directory not being
+/// writable by its owner. Returns the actions taken (empty if healthy).
+fn repair_config_dir_at(dir: &Path) -> Result<Vec<String>, String> {
+    let mut actions = Vec::new();
+
+    if dir.exists() && !dir.is_dir() {
+        let backup = dir.with_extension(format!("bak-{}", now_secs()));
+        fs::rename(dir, &backup).map_err(|e| format!("Cannot back up {}: {e}", dir.display()))?;
+        actions.push(format!("Moved file {} to {}", dir.display(), backup.display()));
+    }
+
+    if !dir.exists() {
+        fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+        actions.push(format!("Created directory {}", dir.display()));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(dir).map_err(|e| e.to_string())?.permissions().mode();
+        if mode & 0o700 != 0o700 {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Cannot fix permissions on {}: {e}", dir.display()))?;
+            actions.push(format!("Reset permissions on {} from {:o} to 755", dir.display(), mode & 0o777));
+        }
+    }
+
+    Ok(actions)
+}
+
+#[tauri::command]
+pub(crate) fn repair_config_dir() -> Result<Vec<String>, String> {
+    let dir = hexdeck_dir().ok_or("Cannot resolve home directory")?;
+    repair_config_dir_at(&dir)
+}
+
+/// Entries of the current state directory that stay behind when `data_dir`
+/// changes: the settings file (it records where `data_dir` points) and the
+/// profile bookkeeping, both of which only live in the profile directory.
+fn data_dir_pinned_paths() -> Vec<PathBuf> {
+    let mut pinned: Vec<PathBuf> = settings_file().into_iter().chain(active_profile_file()).collect();
+    pinned.extend(bootstrap_dir().map(|d| d.join("profiles")));
+    pinned
+}
+
+/// Rename, falling back to copy+delete when crossing filesystems (external
+/// drives). Directories are moved recursively and merge into an existing
+/// destination.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if fs::symlink_metadata(from)?.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            move_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return fs::remove_dir(from);
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Relocate Hexdeck state to `path` (or back to the default for `None`),
+/// moving everything in the current state directory across. The server holds
+/// `server.log` open and is tracked through `server.pid`, so it is stopped
+/// before the move and restarted (if wanted) once the new location is in effect.
+#[tauri::command]
+pub(crate) async fn set_data_dir(app: tauri::AppHandle, path: Option<String>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let target = match path.as_deref() {
+            Some(p) if !Path::new(p).is_absolute() => {
+                return Err(format!("Data directory must be an absolute path: {p}"));
+            }
+            Some(p) => PathBuf::from(p),
+            None => profile_dir().ok_or("Cannot resolve home directory")?,
+        };
+        let current = hexdeck_dir().ok_or("Cannot resolve home directory")?;
+        if target == current {
+            let mut settings = load_settings();
+            settings.data_dir = path;
+            return save_settings(&settings);
+        }
+
+        fs::create_dir_all(&target).map_err(|e| format!("Cannot create {}: {e}", target.display()))?;
+        kill_server();
+        let pinned = data_dir_pinned_paths();
+        let moved = fs::read_dir(&current).map_or(Ok(()), |entries| {
+            entries.flatten().try_for_each(|entry| {
+                let from = entry.path();
+                if pinned.contains(&from) || target.starts_with(&from) {
+                    return Ok(());
+                }
+                move_path(&from, &target.join(entry.file_name()))
+                    .map_err(|e| format!("Cannot move {}: {e}", from.display()))
+            })
+        });
+
+        let saved = moved.and_then(|()| {
+            let mut settings = load_settings();
+            settings.data_dir = path;
+            save_settings(&settings)
+        });
+
+        // Bring the server back even if the move failed part way, so a bad
+        // target doesn't leave the user without one
+        if is_server_wanted() {
+            reset_spawn_attempts();
+            spawn_background("ensure-server", move || ensure_server_running(&app));
+        }
+        saved
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_path_merges_directories() {
+        let root = std::env::temp_dir().join(format!("hexdeck-move-{}", std::process::id()));
+        let (from, to) = (root.join("from"), root.join("to"));
+        fs::create_dir_all(from.join("layouts")).unwrap();
+        fs::create_dir_all(to.join("layouts")).unwrap();
+        fs::write(from.join("layouts").join("desk.json"), "{}").unwrap();
+        fs::write(to.join("layouts").join("home.json"), "{}").unwrap();
+
+        move_path(&from.join("layouts"), &to.join("layouts")).unwrap();
+
+        assert!(!from.join("layouts").exists());
+        assert!(to.join("layouts").join("desk.json").exists());
+        assert!(to.join("layouts").join("home.json").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
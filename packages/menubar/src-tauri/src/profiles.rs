@@ -0,0 +1,117 @@
+//! Settings profiles.
+
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
+use crate::background::spawn_background;
+use crate::paths::{bootstrap_dir, invalidate_hexdeck_dir};
+use crate::server::{ensure_server_running, kill_server, reset_spawn_attempts};
+use crate::settings::{cached_settings, invalidate_settings_cache};
+use crate::shortcuts::register_shortcuts;
+use crate::tray::rebuild_tray_menu;
+use crate::widget::{apply_widget_visibility, SHOW_WIDGET};
+
+pub(crate) const DEFAULT_PROFILE: &str = "default";
+
+pub(crate) fn active_profile_file() -> Option<PathBuf> {
+    bootstrap_dir().map(|d| d.join("active-profile"))
+}
+
+pub(crate) fn active_profile() -> String {
+    active_profile_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| is_valid_name(name))
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(Serialize)]
+pub(crate) struct ProfileInfo {
+    pub(crate) name: String,
+    active: bool,
+}
+
+#[tauri::command]
+pub(crate) fn list_profiles() -> Vec<ProfileInfo> {
+    let active = active_profile();
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    if let Some(entries) = bootstrap_dir().and_then(|d| fs::read_dir(d.join("profiles")).ok()) {
+        let mut named: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| is_valid_name(name) && name != DEFAULT_PROFILE)
+            .collect();
+        named.sort();
+        names.extend(named);
+    }
+    names
+        .into_iter()
+        .map(|name| ProfileInfo { active: name == active, name })
+        .collect()
+}
+
+#[tauri::command]
+pub(crate) fn create_profile(name: String) -> Result<(), String> {
+    if !is_valid_name(&name) || name == DEFAULT_PROFILE {
+        return Err(format!("Invalid profile name: {name}"));
+    }
+    let dir = bootstrap_dir()
+        .ok_or("Cannot resolve home directory")?
+        .join("profiles")
+        .join(&name);
+    if dir.exists() {
+        return Err(format!("Profile already exists: {name}"));
+    }
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())
+}
+
+/// Make `name` the active profile and re-apply everything derived from
+/// settings: widget visibility, shortcuts, menu labels and the server (which
+/// keeps its PID file per profile, so it is restarted under the new one).
+#[tauri::command]
+pub(crate) async fn switch_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if name == active_profile() {
+            return Ok(());
+        }
+        if !list_profiles().iter().any(|p| p.name == name) {
+            return Err(format!("Unknown profile: {name}"));
+        }
+
+        // Stop the server while the old profile's PID file is still in effect
+        kill_server();
+
+        let pointer = active_profile_file().ok_or("Cannot resolve home directory")?;
+        if let Some(parent) = pointer.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&pointer, &name).map_err(|e| e.to_string())?;
+        invalidate_settings_cache();
+        invalidate_hexdeck_dir();
+
+        let settings = cached_settings();
+        SHOW_WIDGET.store(settings.show_widget, Ordering::SeqCst);
+        apply_widget_visibility(&app, settings.show_widget);
+        register_shortcuts(&app)?;
+        rebuild_tray_menu(&app)?;
+
+        reset_spawn_attempts();
+        spawn_background("ensure-server", move || {
+            ensure_server_running(&app);
+        });
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
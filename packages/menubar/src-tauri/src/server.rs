@@ -0,0 +1,1124 @@
+//! Spawning, watching and stopping the server.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+use crate::background::{sleep_unless_shutdown, spawn_background};
+use crate::dashboard::parse_http_url;
+use crate::notifications::notify;
+use crate::paths::hexdeck_dir;
+use crate::settings::{cached_settings, load_settings, save_settings, WidgetSettings};
+use crate::status::emit_fresh_status;
+use crate::tray::set_tray_color;
+use crate::{now_millis, now_secs};
+
+pub(crate) const DEFAULT_SERVER_PORT: u16 = 7433;
+
+/// Port of an externally started server registered via `adopt_server`; 0 when
+/// the menubar manages its own. An adopted server is never spawned or killed.
+static ADOPTED_PORT: AtomicU16 = AtomicU16::new(0);
+
+pub(crate) fn adopted_port() -> Option<u16> {
+    match ADOPTED_PORT.load(Ordering::SeqCst) {
+        0 => None,
+        port => Some(port),
+    }
+}
+
+/// Set by `stop_server` so nothing restarts our server until `restart_server`.
+static SERVER_STOPPED: AtomicBool = AtomicBool::new(false);
+
+/// Whether someone else owns the server process: adopted, run by a service
+/// manager, or remote behind `dashboard_base_url`. We then only observe it.
+fn is_externally_managed() -> bool {
+    let settings = cached_settings();
+    adopted_port().is_some() || settings.server_managed_externally || remote_base_url(&settings).is_some()
+}
+
+/// `dashboard_base_url`, when it points at a server other than our own.
+fn remote_base_url(settings: &WidgetSettings) -> Option<&str> {
+    settings.dashboard_base_url.as_deref().filter(|url| !url.is_empty())
+}
+
+/// Where every request to the server goes.
+pub(crate) struct ServerEndpoint {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) tls: bool,
+}
+
+/// The remote base URL when one is configured, else our own server.
+pub(crate) fn server_endpoint() -> ServerEndpoint {
+    let settings = cached_settings();
+    if let Some(url) = remote_base_url(&settings) {
+        if let Ok((host, port)) = parse_http_url(url) {
+            return ServerEndpoint { host, port, tls: url.starts_with("https://") };
+        }
+    }
+    ServerEndpoint {
+        host: settings.server_host,
+        port: server_port(),
+        tls: false,
+    }
+}
+
+/// Port our own server is spawned on, from settings.
+pub(crate) fn configured_port() -> u16 {
+    cached_settings().server_port
+}
+
+/// Port the server is expected on: the adopted one if any, else our own.
+pub(crate) fn server_port() -> u16 {
+    adopted_port().unwrap_or_else(configured_port)
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PidInfo {
+    pub(crate) pid: u64,
+    pub(crate) port: u16,
+}
+
+/// `PidInfo` plus when the server wrote the file, for the debug UI.
+#[derive(Serialize)]
+pub(crate) struct PidFileInfo {
+    #[serde(flatten)]
+    info: PidInfo,
+    modified_ms: Option<u64>,
+}
+
+/// Written by the server on startup and removed on clean exit.
+pub(crate) fn pid_file() -> Option<PathBuf> {
+    hexdeck_dir().map(|d| d.join("server.pid"))
+}
+
+/// Resolve the configured host and try every address it maps to, so a
+/// `localhost` that resolves to `::1` first still finds an IPv4-only server.
+pub(crate) fn is_server_reachable() -> bool {
+    let endpoint = server_endpoint();
+    is_addr_reachable(&endpoint.host, endpoint.port)
+}
+
+fn is_server_reachable_on(port: u16) -> bool {
+    is_addr_reachable(&cached_settings().server_host, port)
+}
+
+fn is_addr_reachable(host: &str, port: u16) -> bool {
+    let Ok(addrs) = (host, port).to_socket_addrs() else {
+        return false;
+    };
+    addrs
+        .into_iter()
+        .any(|addr| TcpStream::connect_timeout(&addr, Duration::from_secs(2)).is_ok())
+}
+
+pub(crate) fn load_pid_info() -> Option<PidInfo> {
+    let path = pid_file()?;
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+pub(crate) fn is_pid_running(pid: u64) -> bool {
+    unsafe { libc::kill(pid as i32, 0) == 0 }
+}
+
+fn resource_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resource_dir()
+        .map_err(|e| format!("Cannot resolve resource dir: {e}"))
+}
+
+/// Where the server binary path came from.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ServerSource {
+    Settings,
+    Env,
+    Resource,
+}
+
+/// File name of the server binary bundled in the resource dir.
+const BUNDLED_SERVER_BINARY: &str = "hexdeck-server";
+
+/// The settings override, then `$HEXDECK_SERVER_BIN`, if either is set.
+fn server_binary_override() -> Option<(PathBuf, ServerSource)> {
+    if let Some(path) = cached_settings().server_binary.filter(|p| !p.is_empty()) {
+        return Some((PathBuf::from(path), ServerSource::Settings));
+    }
+    std::env::var_os("HEXDECK_SERVER_BIN")
+        .filter(|p| !p.is_empty())
+        .map(|path| (PathBuf::from(path), ServerSource::Env))
+}
+
+/// Resolve the server binary: settings override, then `$HEXDECK_SERVER_BIN`,
+/// then the copy bundled in the resource dir.
+pub(crate) fn resolve_server_binary(app: &tauri::AppHandle) -> Result<(PathBuf, ServerSource), String> {
+    match server_binary_override() {
+        Some(resolved) => Ok(resolved),
+        None => Ok((resource_dir(app)?.join(BUNDLED_SERVER_BINARY), ServerSource::Resource)),
+    }
+}
+
+/// Whether the override is a `#!` script, which runs under its interpreter's
+/// process name rather than its own.
+fn server_binary_is_script() -> bool {
+    let Some((path, _)) = server_binary_override() else {
+        return false;
+    };
+    let mut magic = [0u8; 2];
+    fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut magic))
+        .is_ok()
+        && magic == *b"#!"
+}
+
+pub(crate) fn server_binary_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    resolve_server_binary(app).map(|(path, _)| path)
+}
+
+#[derive(Serialize)]
+pub(crate) struct ServerSourceInfo {
+    pub(crate) path: String,
+    pub(crate) source: ServerSource,
+    pub(crate) exists: bool,
+    pub(crate) size: Option<u64>,
+    modified_ms: Option<u64>,
+}
+
+/// Outcome of checking the server binary against its shipped hash.
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub(crate) enum BinaryIntegrity {
+    Match { sha256: String },
+    Mismatch { expected: String, actual: String },
+    /// No `.sha256` next to the binary (dev builds, custom binaries).
+    NoExpectedHash { actual: String },
+}
+
+/// Expected hash file: `<binary>.sha256`, as staged by the release workflow.
+fn expected_hash_file(binary: &Path) -> PathBuf {
+    let mut name = binary.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+fn check_binary_integrity(binary: &Path) -> Result<BinaryIntegrity, String> {
+    let actual = sha256_file(binary).map_err(|e| format!("Cannot read {}: {e}", binary.display()))?;
+    // `shasum` output is "<hash>  <file>"; a bare hash works too
+    let expected = fs::read_to_string(expected_hash_file(binary))
+        .ok()
+        .and_then(|text| text.split_whitespace().next().map(str::to_ascii_lowercase));
+    Ok(match expected {
+        None => BinaryIntegrity::NoExpectedHash { actual },
+        Some(expected) if expected == actual => BinaryIntegrity::Match { sha256: actual },
+        Some(expected) => BinaryIntegrity::Mismatch { expected, actual },
+    })
+}
+
+/// Hash the resolved server binary and compare it with the `.sha256` shipped
+/// beside it, to catch corrupted or partial installs.
+#[tauri::command]
+pub(crate) async fn verify_server_binary(app: tauri::AppHandle) -> Result<BinaryIntegrity, String> {
+    let binary = server_binary_path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || check_binary_integrity(&binary))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Which server binary `spawn_server` would run, and why.
+#[tauri::command]
+pub(crate) fn get_server_source(app: tauri::AppHandle) -> Result<ServerSourceInfo, String> {
+    let (path, source) = resolve_server_binary(&app)?;
+    let metadata = fs::metadata(&path).ok();
+    Ok(ServerSourceInfo {
+        path: path.to_string_lossy().into_owned(),
+        source,
+        exists: metadata.is_some(),
+        size: metadata.as_ref().map(|m| m.len()),
+        modified_ms: metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64),
+    })
+}
+
+/// PIDs of processes listening on `port`, via `lsof`.
+pub(crate) fn pids_on_port(port: u16) -> Vec<i32> {
+    let Ok(output) = std::process::Command::new("lsof")
+        .args(["-ti", &format!(":{}", port)])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().parse::<i32>().ok())
+        .collect()
+}
+
+/// Executable name of a running process, via `ps`.
+pub(crate) fn process_name(pid: i32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "comm="])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn spawn_server(app: &tauri::AppHandle) -> Result<(), String> {
+    let resource_dir = resource_dir(app)?;
+
+    let binary = server_binary_path(app)?;
+    if !binary.exists() {
+        return Err(format!("Server binary not found at {}", binary.display()));
+    }
+    if cached_settings().verify_server_binary {
+        if let BinaryIntegrity::Mismatch { expected, actual } = check_binary_integrity(&binary)? {
+            let _ = notify(
+                app,
+                "Hexdeck server binary is damaged",
+                "Its checksum doesn't match the one it shipped with. Reinstall Hexdeck.",
+            );
+            return Err(format!(
+                "Server binary checksum mismatch (expected {expected}, got {actual}); not starting it"
+            ));
+        }
+    }
+
+    // Ensure executable
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&binary, fs::Permissions::from_mode(0o755));
+    }
+
+    let settings = cached_settings();
+    let env = server_env(&settings, hexdeck_dir())?;
+
+    let cwd = server_working_dir(&settings, hexdeck_dir()).ok_or("Cannot resolve home directory")?;
+    fs::create_dir_all(&cwd).map_err(|e| format!("Cannot create {}: {e}", cwd.display()))?;
+
+    let dashboard_dir = resource_dir.join("dashboard");
+    let mut cmd = std::process::Command::new(&binary);
+    cmd.current_dir(&cwd);
+    cmd.arg("--port").arg(settings.server_port.to_string());
+    apply_server_env(&mut cmd, settings.server_env_clear, &env);
+    if dashboard_dir.exists() {
+        cmd.arg("--dashboard-dir")
+            .arg(dashboard_dir.to_string_lossy().as_ref());
+    }
+
+    cmd.stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn server: {e}"))?;
+
+    Ok(())
+}
+
+/// Variables set on the server on top of (or, with `server_env_clear`,
+/// instead of) ours: `server_env` plus `HEXDECK_HOME` pointing at
+/// `state_dir`, so the server's PID file and log land next to ours.
+fn server_env(
+    settings: &WidgetSettings,
+    state_dir: Option<PathBuf>,
+) -> Result<BTreeMap<String, String>, String> {
+    if let Some(key) = settings
+        .server_env
+        .keys()
+        .find(|k| k.is_empty() || k.contains('=') || k.contains('\0'))
+    {
+        return Err(format!("Invalid server_env key: {key:?}"));
+    }
+    let mut env: BTreeMap<String, String> = settings
+        .server_env
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    if let Some(dir) = state_dir {
+        env.insert("HEXDECK_HOME".into(), dir.to_string_lossy().into_owned());
+    }
+    Ok(env)
+}
+
+fn apply_server_env(cmd: &mut std::process::Command, clear: bool, env: &BTreeMap<String, String>) {
+    if clear {
+        cmd.env_clear();
+    }
+    cmd.envs(env);
+}
+
+/// Where the server runs: `server_cwd`, else `state_dir`. A GUI launch
+/// would otherwise leave it in whatever cwd we inherited (often `/`).
+fn server_working_dir(settings: &WidgetSettings, state_dir: Option<PathBuf>) -> Option<PathBuf> {
+    settings
+        .server_cwd
+        .as_deref()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or(state_dir)
+}
+
+/// Set (or with `None`, reset to the state directory) the server's working
+/// directory. Takes effect on the next spawn.
+#[tauri::command]
+pub(crate) fn set_server_cwd(path: Option<String>) -> Result<(), String> {
+    let path = path.filter(|p| !p.is_empty());
+    if let Some(p) = path.as_deref() {
+        if !Path::new(p).is_absolute() {
+            return Err(format!("Server working directory must be an absolute path: {p}"));
+        }
+        fs::create_dir_all(p).map_err(|e| format!("Cannot create {p}: {e}"))?;
+    }
+    let mut settings = load_settings();
+    settings.server_cwd = path;
+    save_settings(&settings)
+}
+
+/// SIGTERM, wait up to 3s for graceful shutdown (removeHooks + cleanup),
+/// then escalate to SIGKILL if still alive.
+fn terminate_process(pid: u64) {
+    unsafe { libc::kill(pid as i32, libc::SIGTERM); }
+    for _ in 0..30 {
+        std::thread::sleep(Duration::from_millis(100));
+        if !is_pid_running(pid) {
+            return;
+        }
+    }
+    if is_pid_running(pid) {
+        unsafe { libc::kill(pid as i32, libc::SIGKILL); }
+    }
+}
+
+/// Whether `process_name`, as `ps -o comm=` reports it, is the server binary
+/// we would spawn.
+pub(crate) fn looks_like_hexdeck_server(process_name: &str) -> bool {
+    let binary = server_binary_override()
+        .and_then(|(path, _)| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| BUNDLED_SERVER_BINARY.to_string());
+    process_name_matches(process_name, &binary)
+}
+
+/// `comm` is the full path on macOS but the file name cut to 15 bytes on
+/// Linux, so only (the start of) the file name is compared.
+fn process_name_matches(process_name: &str, binary: &str) -> bool {
+    let name = Path::new(process_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(process_name);
+    name == binary || (name.len() == 15 && binary.starts_with(name))
+}
+
+/// Whether nothing is listening on 127.0.0.1:`port`, by briefly binding it.
+#[tauri::command]
+pub(crate) fn is_port_available(port: u16) -> bool {
+    std::net::TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+/// Move our server to `port`, restarting it there. Refuses a port that is
+/// already taken, saying whether the holder looks like a Hexdeck server.
+#[tauri::command]
+pub(crate) async fn set_server_port(app: tauri::AppHandle, port: u16) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if port == 0 {
+            return Err("Invalid port: 0".into());
+        }
+        let mut settings = load_settings();
+        if settings.server_port == port {
+            return Ok(());
+        }
+        if !is_port_available(port) {
+            let owners: Vec<(i32, String)> = pids_on_port(port)
+                .into_iter()
+                .filter_map(|pid| process_name(pid).map(|name| (pid, name)))
+                .collect();
+            let hexdeck = owners.iter().any(|(_, name)| looks_like_hexdeck_server(name));
+            let owners = owners
+                .iter()
+                .map(|(pid, name)| format!("{name} ({pid})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(if hexdeck {
+                format!("Port {port} is already used by another Hexdeck server: {owners}")
+            } else if owners.is_empty() {
+                format!("Port {port} is already in use")
+            } else {
+                format!("Port {port} is in use by {owners}")
+            });
+        }
+
+        // Stop the server on the old port before the setting moves on
+        kill_server();
+        settings.server_port = port;
+        save_settings(&settings)?;
+        reset_spawn_attempts();
+        spawn_background("ensure-server", move || {
+            ensure_server_running(&app);
+        });
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize)]
+pub(crate) struct KilledProcess {
+    pub(crate) pid: i32,
+    pub(crate) name: String,
+}
+
+/// Terminate whatever is listening on `port`, for orphaned servers whose PID
+/// file is gone. Refuses to touch non-hexdeck processes unless `force` is set;
+/// nothing is killed if any owner is refused.
+#[tauri::command]
+pub(crate) async fn kill_process_on_port(port: u16, force: bool) -> Result<Vec<KilledProcess>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let owners: Vec<KilledProcess> = pids_on_port(port)
+            .into_iter()
+            .map(|pid| KilledProcess {
+                pid,
+                name: process_name(pid).unwrap_or_else(|| "unknown".to_string()),
+            })
+            .collect();
+
+        if !force {
+            if let Some(other) = owners.iter().find(|p| !looks_like_hexdeck_server(&p.name)) {
+                return Err(format!(
+                    "Port {port} is held by {} (pid {}), not hexdeck-server; use force to kill it",
+                    other.name, other.pid
+                ));
+            }
+        }
+
+        for owner in &owners {
+            terminate_process(owner.pid as u64);
+        }
+        Ok(owners)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Kill the server process if we spawned it (reads PID from disk).
+/// Falls back to killing by port if the PID file is missing/stale.
+pub(crate) fn kill_server() {
+    kill_server_on(configured_port());
+}
+
+/// `kill_server` with the port fallback aimed at `port`, for when the setting
+/// has already moved on.
+pub(crate) fn kill_server_on(port: u16) {
+    if is_externally_managed() {
+        return;
+    }
+    SERVER_UP_SINCE_MS.store(0, Ordering::SeqCst);
+    let mut killed_by_pid = false;
+
+    if let Some(info) = load_pid_info() {
+        if is_pid_running(info.pid) {
+            terminate_process(info.pid);
+            killed_by_pid = true;
+        }
+    }
+
+    // Fallback: find and kill our server if it's listening on the port.
+    // Handles cases where PID file is missing (crash, force-quit, dev mode).
+    // The port is user-configurable, so anything else holding it is left alone.
+    if !killed_by_pid {
+        let ours = pids_on_port(port)
+            .into_iter()
+            .filter(|pid| process_name(*pid).is_some_and(|name| looks_like_hexdeck_server(&name)));
+        for pid in ours {
+            unsafe { libc::kill(pid, libc::SIGTERM); }
+        }
+    }
+
+    // Clean up PID file
+    if let Some(path) = pid_file() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Tracks the epoch-seconds of the last spawn attempt.
+/// Prevents rapid re-spawning but allows retry after a backoff.
+static LAST_SPAWN_ATTEMPT: AtomicU64 = AtomicU64::new(0);
+/// Failed spawns since the server was last seen up; capped by
+/// `max_spawn_attempts_per_session`.
+static SPAWN_ATTEMPTS: AtomicU64 = AtomicU64::new(0);
+const SPAWN_COOLDOWN_SECS: u64 = 30;
+const MAX_SPAWN_BACKOFF_SECS: u64 = 8 * SPAWN_COOLDOWN_SECS;
+
+/// Wait required after `attempts` failed spawns: the base cooldown, doubling
+/// per failure up to MAX_SPAWN_BACKOFF_SECS.
+fn spawn_backoff_secs(attempts: u64) -> u64 {
+    let doublings = attempts.saturating_sub(1).min(8) as u32;
+    (SPAWN_COOLDOWN_SECS << doublings).min(MAX_SPAWN_BACKOFF_SECS)
+}
+
+/// Whether `attempts` failed spawns use up the session's budget; a budget
+/// of 0 is unlimited.
+fn spawn_budget_spent(attempts: u64, max_attempts: u64) -> bool {
+    max_attempts > 0 && attempts >= max_attempts
+}
+
+/// Forget past spawn failures so the next ensure spawns immediately.
+pub(crate) fn reset_spawn_attempts() {
+    SPAWN_ATTEMPTS.store(0, Ordering::SeqCst);
+    LAST_SPAWN_ATTEMPT.store(0, Ordering::SeqCst);
+}
+
+/// The spawn guard as `ensure_server_running` sees it.
+#[derive(Serialize)]
+pub(crate) struct SpawnState {
+    /// Failed spawns since the server was last seen up.
+    attempts: u64,
+    max_attempts: u64,
+    /// The session's budget is spent; nothing more is spawned until cleared.
+    gave_up: bool,
+    /// Epoch-seconds of the last attempt, if any.
+    last_attempt_secs: Option<u64>,
+    /// Seconds until the backoff allows another attempt; 0 if it already does.
+    retry_in_secs: u64,
+    /// Held down by `stop_server`.
+    stopped: bool,
+}
+
+/// Inspect the spawn guard, e.g. to explain why the server "gave up".
+#[tauri::command]
+pub(crate) fn get_spawn_state() -> SpawnState {
+    let attempts = SPAWN_ATTEMPTS.load(Ordering::SeqCst);
+    let max_attempts = cached_settings().max_spawn_attempts_per_session;
+    let last = LAST_SPAWN_ATTEMPT.load(Ordering::SeqCst);
+    let retry_at = last + spawn_backoff_secs(attempts);
+    SpawnState {
+        attempts,
+        max_attempts,
+        gave_up: spawn_budget_spent(attempts, max_attempts),
+        last_attempt_secs: (last > 0).then_some(last),
+        retry_in_secs: if last > 0 { retry_at.saturating_sub(now_secs()) } else { 0 },
+        stopped: SERVER_STOPPED.load(Ordering::SeqCst),
+    }
+}
+
+/// Reset the spawn guard like `force_reconnect` does, but without spawning
+/// or reconnecting: the next watchdog tick may spawn straight away. A
+/// `stop_server` stays in effect.
+#[tauri::command]
+pub(crate) fn clear_spawn_state() -> SpawnState {
+    reset_spawn_attempts();
+    get_spawn_state()
+}
+
+/// Whether a live PID whose port the caller found unreachable should be
+/// given up on: its PID file is older than `pid_staleness_secs`, so it is no
+/// longer a server starting up but (likely) a PID the OS recycled, which we
+/// would otherwise wait on forever. A process known to be something else is
+/// stale straight away; a script override runs under its interpreter, so
+/// then the name proves nothing and only the age counts.
+fn is_pid_file_stale(pid: u64) -> bool {
+    let foreign = !server_binary_is_script()
+        && process_name(pid as i32).is_some_and(|name| !looks_like_hexdeck_server(&name));
+    if foreign {
+        return true;
+    }
+    let max_age_ms = cached_settings().pid_staleness_secs.saturating_mul(1000);
+    pid_file_modified_ms().is_some_and(|modified| now_millis().saturating_sub(modified) > max_age_ms)
+}
+
+pub(crate) fn ensure_server_running(app: &tauri::AppHandle) {
+    if is_externally_managed() || SERVER_STOPPED.load(Ordering::SeqCst) {
+        return;
+    }
+    if is_server_reachable() {
+        SPAWN_ATTEMPTS.store(0, Ordering::SeqCst);
+        observe_server(true);
+        return;
+    }
+
+    // Clean stale PID
+    if let Some(info) = load_pid_info() {
+        if !is_pid_running(info.pid) || is_pid_file_stale(info.pid) {
+            if let Some(path) = pid_file() {
+                let _ = fs::remove_file(path);
+            }
+        } else {
+            // PID running but port not reachable yet — wait a bit
+            for _ in 0..10 {
+                if !sleep_unless_shutdown(Duration::from_millis(500)) {
+                    return;
+                }
+                if is_server_reachable() {
+                    SPAWN_ATTEMPTS.store(0, Ordering::SeqCst);
+                    return;
+                }
+            }
+        }
+    }
+
+    // Bounded retries: stop once the session's budget is spent
+    let attempts = SPAWN_ATTEMPTS.load(Ordering::SeqCst);
+    let max_attempts = cached_settings().max_spawn_attempts_per_session;
+    if spawn_budget_spent(attempts, max_attempts) {
+        return;
+    }
+
+    // Rate-limit spawn attempts: skip until the backoff since the last one has passed
+    let last = LAST_SPAWN_ATTEMPT.load(Ordering::SeqCst);
+    let now = now_secs();
+    if last > 0 && now.saturating_sub(last) < spawn_backoff_secs(attempts) {
+        return;
+    }
+    LAST_SPAWN_ATTEMPT.store(now, Ordering::SeqCst);
+
+    // Spawn and wait for it to become reachable
+    let spawned_at = Instant::now();
+    let error = match spawn_server(app) {
+        Err(e) => e,
+        Ok(()) => {
+            // Poll finely so the measured startup latency is meaningful
+            for _ in 0..50 {
+                if !sleep_unless_shutdown(Duration::from_millis(100)) {
+                    return;
+                }
+                if is_server_reachable() {
+                    SPAWN_ATTEMPTS.store(0, Ordering::SeqCst);
+                    record_startup_latency(app, spawned_at.elapsed());
+                    return;
+                }
+            }
+            "server spawned but not reachable after 5s".to_string()
+        }
+    };
+    menubar_log!("{error}");
+
+    let attempts = SPAWN_ATTEMPTS.fetch_add(1, Ordering::SeqCst) + 1;
+    if attempts == max_attempts {
+        let _ = notify(
+            app,
+            "Hexdeck server failed to start",
+            &format!("Gave up after {attempts} attempts: {error}. Restart the server to try again."),
+        );
+    }
+}
+
+/// Time from `spawn_server` to first successful connect for the last spawn
+/// we started; 0 until one has been measured.
+static STARTUP_LATENCY_MS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Serialize)]
+struct ServerReady {
+    startup_latency_ms: u64,
+}
+
+fn record_startup_latency(app: &tauri::AppHandle, latency: Duration) {
+    let ms = latency.as_millis() as u64;
+    STARTUP_LATENCY_MS.store(ms, Ordering::SeqCst);
+    SERVER_UP_SINCE_MS.store(now_millis(), Ordering::SeqCst);
+    menubar_log!("server reachable {ms}ms after spawn");
+    let _ = app.emit("server-ready", ServerReady { startup_latency_ms: ms });
+}
+
+#[tauri::command]
+pub(crate) fn get_startup_latency_ms() -> Option<u64> {
+    match STARTUP_LATENCY_MS.load(Ordering::SeqCst) {
+        0 => None,
+        ms => Some(ms),
+    }
+}
+
+/// Set once the user has asked for the server (opened the popup or the
+/// dashboard); with `prestart_server` off, the watchdog leaves it down until
+/// then.
+pub(crate) static SERVER_DEMANDED: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn is_server_wanted() -> bool {
+    SERVER_DEMANDED.load(Ordering::SeqCst) || cached_settings().prestart_server
+}
+
+/// The user did something that needs the server: start it now.
+pub(crate) fn demand_server(app: &tauri::AppHandle) {
+    if SERVER_DEMANDED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    spawn_background("ensure-server", move || ensure_server_running(&app));
+}
+
+/// Called by the webviews when their event stream drops. Both load at
+/// launch, so this isn't a user request and doesn't count as demand: with
+/// `prestart_server` off it does nothing until the user opens something.
+#[tauri::command]
+pub(crate) fn ensure_server(app: tauri::AppHandle) {
+    if !is_server_wanted() {
+        return;
+    }
+    spawn_background("ensure-server", move || {
+        ensure_server_running(&app);
+    });
+}
+
+/// Register an already-running server on `port` (started by hand or by
+/// another tool) instead of spawning our own. Fails if nothing answers there.
+#[tauri::command]
+pub(crate) async fn adopt_server(app: tauri::AppHandle, port: u16) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        if port == 0 {
+            return Err("Invalid port: 0".into());
+        }
+        if !is_server_reachable_on(port) {
+            let _ = set_tray_color(&app, "red");
+            return Err(format!("No server reachable on port {port}"));
+        }
+        ADOPTED_PORT.store(port, Ordering::SeqCst);
+        set_tray_color(&app, "green")?;
+        emit_fresh_status(&app);
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Resolve `program` like a shell would: a path as-is, a bare name on `$PATH`.
+fn find_executable(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let dirs = std::env::var_os("PATH")?;
+    std::env::split_paths(&dirs)
+        .flat_map(|dir| {
+            let plain = dir.join(program);
+            let exe = dir.join(format!("{program}.exe"));
+            [plain, exe]
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+fn validate_service_command(argv: &[String]) -> Result<(), String> {
+    let program = argv.first().ok_or("Service command is empty")?;
+    find_executable(program)
+        .map(|_| ())
+        .ok_or_else(|| format!("Service command not found: {program}"))
+}
+
+/// Service managers normally answer in well under a second; past this the
+/// command is assumed hung and killed.
+const SERVICE_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run a service-manager command to completion; `name` is for errors.
+fn run_service_command(name: &str, argv: &[String]) -> Result<(), String> {
+    if argv.is_empty() {
+        return Err(format!("No {name} command configured for the externally managed server"));
+    }
+    validate_service_command(argv)?;
+    let mut child = std::process::Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{name} command failed to run: {e}"))?;
+    let deadline = Instant::now() + SERVICE_COMMAND_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if Instant::now() >= deadline || !sleep_unless_shutdown(Duration::from_millis(100)) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{name} command didn't finish within {}s", SERVICE_COMMAND_TIMEOUT.as_secs()));
+        }
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        Err(format!("{name} command exited with {status}: {}", stderr.trim()))
+    }
+}
+
+/// Poll up to 5s for the server to answer.
+fn wait_until_reachable() -> bool {
+    for _ in 0..50 {
+        if is_server_reachable() {
+            return true;
+        }
+        if !sleep_unless_shutdown(Duration::from_millis(100)) {
+            break;
+        }
+    }
+    false
+}
+
+/// Restart the server. The tray shows orange ("restarting") until the new
+/// server answers, then green, or red if it never comes up. A server under a
+/// service manager is restarted through `service_restart_command`.
+#[tauri::command]
+pub(crate) fn restart_server(app: tauri::AppHandle) {
+    spawn_background("restart-server", move || {
+        let _ = set_tray_color(&app, "orange");
+        SERVER_STOPPED.store(false, Ordering::SeqCst);
+        SERVER_DEMANDED.store(true, Ordering::SeqCst);
+        let settings = cached_settings();
+        if settings.server_managed_externally {
+            if let Err(e) = run_service_command("restart", &settings.service_restart_command) {
+                menubar_log!("{e}");
+                let _ = notify(&app, "Hexdeck server restart failed", &e);
+            }
+            wait_until_reachable();
+        } else {
+            kill_server();
+            // An explicit restart shouldn't wait out the backoff or spent budget
+            reset_spawn_attempts();
+            ensure_server_running(&app);
+        }
+        let color = if is_server_reachable() { "green" } else { "red" };
+        let _ = set_tray_color(&app, color);
+        emit_fresh_status(&app);
+    });
+}
+
+/// Try to get back to a live server now: forget spawn failures and any
+/// `stop_server`, ensure it's running, and tell the frontend to reopen its
+/// event stream.
+#[tauri::command]
+pub(crate) fn force_reconnect(app: tauri::AppHandle) {
+    SERVER_STOPPED.store(false, Ordering::SeqCst);
+    SERVER_DEMANDED.store(true, Ordering::SeqCst);
+    reset_spawn_attempts();
+    let _ = app.emit("force-reconnect", ());
+    spawn_background("ensure-server", move || {
+        ensure_server_running(&app);
+        let color = if is_server_reachable() { "green" } else { "red" };
+        let _ = set_tray_color(&app, color);
+        emit_fresh_status(&app);
+    });
+}
+
+/// Stop the server and keep it down until `restart_server`. A server under a
+/// service manager is stopped through `service_stop_command`.
+#[tauri::command]
+pub(crate) async fn stop_server(app: tauri::AppHandle) -> Result<(), String> {
+    if adopted_port().is_some() {
+        return Err("The adopted server isn't ours to stop".into());
+    }
+    SERVER_STOPPED.store(true, Ordering::SeqCst);
+    tauri::async_runtime::spawn_blocking(move || {
+        let settings = cached_settings();
+        if settings.server_managed_externally {
+            run_service_command("stop", &settings.service_stop_command)?;
+        } else {
+            kill_server();
+        }
+        set_tray_color(&app, "grey")?;
+        emit_fresh_status(&app);
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Hand the server over to (or take it back from) a service manager. Each
+/// configured command's program must exist.
+#[tauri::command]
+pub(crate) fn set_server_managed_externally(
+    app: tauri::AppHandle,
+    enabled: bool,
+    restart_command: Vec<String>,
+    stop_command: Vec<String>,
+) -> Result<(), String> {
+    for argv in [&restart_command, &stop_command] {
+        if !argv.is_empty() {
+            validate_service_command(argv)?;
+        }
+    }
+    let mut settings = load_settings();
+    settings.server_managed_externally = enabled;
+    settings.service_restart_command = restart_command;
+    settings.service_stop_command = stop_command;
+    save_settings(&settings)?;
+    if !enabled {
+        ensure_server(app);
+    }
+    Ok(())
+}
+
+fn pid_file_modified_ms() -> Option<u64> {
+    pid_file()
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|age| age.as_millis() as u64)
+}
+
+#[tauri::command]
+pub(crate) fn get_pid_file_info() -> Option<PidFileInfo> {
+    let info = load_pid_info()?;
+    Some(PidFileInfo {
+        info,
+        modified_ms: pid_file_modified_ms(),
+    })
+}
+
+/// Epoch-millis since which the current server has been up; 0 while it is
+/// down or not yet observed.
+pub(crate) static SERVER_UP_SINCE_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Update the uptime baseline from a reachability probe. A server we didn't
+/// watch start (already running, adopted) is dated by its PID file, which it
+/// writes on startup; failing that, by when we first saw it.
+pub(crate) fn observe_server(reachable: bool) {
+    if !reachable {
+        SERVER_UP_SINCE_MS.store(0, Ordering::SeqCst);
+        return;
+    }
+    if SERVER_UP_SINCE_MS.load(Ordering::SeqCst) == 0 {
+        let since = pid_file_modified_ms()
+            .filter(|_| load_pid_info().is_some_and(|info| is_pid_running(info.pid)))
+            .unwrap_or_else(now_millis);
+        let _ = SERVER_UP_SINCE_MS.compare_exchange(0, since, Ordering::SeqCst, Ordering::SeqCst);
+    }
+}
+
+/// How long the server has been up, or `None` if it's down. Probes the
+/// server (and may read its PID file and process table), so off the main
+/// thread.
+#[tauri::command]
+pub(crate) async fn get_server_uptime_ms() -> Result<Option<u64>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let reachable = is_server_reachable();
+        observe_server(reachable);
+        reachable.then(|| now_millis().saturating_sub(SERVER_UP_SINCE_MS.load(Ordering::SeqCst)))
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn server_env_includes_settings_and_home() {
+        let settings = WidgetSettings {
+            server_env: HashMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+            ..WidgetSettings::default()
+        };
+        let env = server_env(&settings, Some(PathBuf::from("/tmp/hexdeck-state"))).unwrap();
+        assert_eq!(env.get("RUST_LOG").map(String::as_str), Some("debug"));
+        assert_eq!(env.get("HEXDECK_HOME").map(String::as_str), Some("/tmp/hexdeck-state"));
+    }
+
+    #[test]
+    fn server_env_rejects_invalid_keys() {
+        for key in ["", "A=B", "A\0B"] {
+            let settings = WidgetSettings {
+                server_env: HashMap::from([(key.to_string(), "x".to_string())]),
+                ..WidgetSettings::default()
+            };
+            assert!(server_env(&settings, None).is_err(), "accepted {key:?}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn server_env_reaches_the_child() {
+        let settings = WidgetSettings {
+            server_env: HashMap::from([("HEXDECK_TEST_VAR".to_string(), "42".to_string())]),
+            ..WidgetSettings::default()
+        };
+        let env = server_env(&settings, None).unwrap();
+        let mut cmd = std::process::Command::new("/usr/bin/env");
+        apply_server_env(&mut cmd, true, &env);
+        let output = cmd.output().unwrap();
+        let printed = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(printed.trim(), "HEXDECK_TEST_VAR=42");
+    }
+
+    #[test]
+    fn server_working_dir_defaults_to_state_dir() {
+        let settings = WidgetSettings::default();
+        let state = PathBuf::from("/tmp/hexdeck-state");
+        assert_eq!(server_working_dir(&settings, Some(state.clone())), Some(state));
+        assert_eq!(server_working_dir(&settings, None), None);
+    }
+
+    #[test]
+    fn server_working_dir_prefers_server_cwd() {
+        let settings = WidgetSettings {
+            server_cwd: Some("/srv/hexdeck".into()),
+            ..WidgetSettings::default()
+        };
+        assert_eq!(
+            server_working_dir(&settings, Some(PathBuf::from("/tmp/hexdeck-state"))),
+            Some(PathBuf::from("/srv/hexdeck"))
+        );
+    }
+
+    #[test]
+    fn server_working_dir_ignores_empty_server_cwd() {
+        let settings = WidgetSettings {
+            server_cwd: Some(String::new()),
+            ..WidgetSettings::default()
+        };
+        let state = PathBuf::from("/tmp/hexdeck-state");
+        assert_eq!(server_working_dir(&settings, Some(state.clone())), Some(state));
+    }
+
+    #[test]
+    fn spawn_backoff_doubles_from_the_cooldown() {
+        assert_eq!(spawn_backoff_secs(0), SPAWN_COOLDOWN_SECS);
+        assert_eq!(spawn_backoff_secs(1), SPAWN_COOLDOWN_SECS);
+        assert_eq!(spawn_backoff_secs(2), 2 * SPAWN_COOLDOWN_SECS);
+        assert_eq!(spawn_backoff_secs(3), 4 * SPAWN_COOLDOWN_SECS);
+    }
+
+    #[test]
+    fn spawn_backoff_is_capped() {
+        assert_eq!(spawn_backoff_secs(4), MAX_SPAWN_BACKOFF_SECS);
+        assert_eq!(spawn_backoff_secs(50), MAX_SPAWN_BACKOFF_SECS);
+        assert_eq!(spawn_backoff_secs(u64::MAX), MAX_SPAWN_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn process_name_matches_binary_file_name() {
+        assert!(process_name_matches("hexdeck-server", "hexdeck-server"));
+        assert!(process_name_matches("/Applications/Hexdeck.app/Contents/Resources/hexdeck-server", "hexdeck-server"));
+        assert!(!process_name_matches("node", "hexdeck-server"));
+        assert!(!process_name_matches("hexdeck", "hexdeck-server"));
+    }
+
+    #[test]
+    fn process_name_matches_truncated_linux_comm() {
+        assert!(process_name_matches("hexdeck-server-", "hexdeck-server-dev"));
+        assert!(!process_name_matches("hexdeck-server", "hexdeck-server-dev"));
+    }
+}
@@ -0,0 +1,162 @@
+//! Talking to the server over HTTP.
+
+use serde::Serialize;
+use std::io::Read;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::server::{server_endpoint, ServerEndpoint};
+use crate::settings::{load_settings, save_settings};
+
+// ─── Server HTTP ───────────────────────────────────────────────────────────
+
+pub(crate) const HTTP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Minimal HTTP/1.1 request to the server at `server_endpoint`, returning
+/// status and body. Enough for its small JSON endpoints; doesn't decode
+/// chunked bodies.
+fn server_request(method: &str, path: &str, body: Option<&str>) -> Result<(u16, String), String> {
+    let ServerEndpoint { host, port, tls } = server_endpoint();
+    let addrs = (host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?;
+    let mut stream = addrs
+        .into_iter()
+        .find_map(|addr| TcpStream::connect_timeout(&addr, HTTP_TIMEOUT).ok())
+        .ok_or_else(|| format!("Server not reachable on {host}:{port}"))?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT)).map_err(|e| e.to_string())?;
+    let host_header = format!("{host}:{port}");
+    if tls {
+        let connector = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+        let mut stream = connector.connect(&host, stream).map_err(|e| e.to_string())?;
+        http_exchange(&mut stream, method, &host_header, path, body)
+    } else {
+        http_exchange(&mut stream, method, &host_header, path, body)
+    }
+}
+
+/// Send one request over an open connection (plain or TLS) and read the
+/// whole response.
+pub(crate) fn http_exchange(
+    stream: &mut (impl Read + std::io::Write),
+    method: &str,
+    host: &str,
+    path: &str,
+    body: Option<&str>,
+) -> Result<(u16, String), String> {
+    let body = body.unwrap_or("");
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(|e| e.to_string())?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or("Malformed HTTP response")?;
+    Ok((status, body.to_string()))
+}
+
+/// Endpoint used to confirm the server actually serves requests.
+const HEALTH_PATH: &str = "/api/health";
+
+#[derive(Serialize)]
+pub(crate) struct PingResult {
+    pub(crate) status: u16,
+    pub(crate) latency_ms: u64,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub(crate) enum PingError {
+    /// No connection, timeout, or an unparseable response.
+    Unreachable(String),
+    /// The server answered, but with a non-2xx status.
+    BadStatus(u16),
+}
+
+/// One real request to the server's health endpoint.
+pub(crate) fn ping_health() -> Result<PingResult, PingError> {
+    let started = Instant::now();
+    let (status, _) = server_request("GET", HEALTH_PATH, None).map_err(PingError::Unreachable)?;
+    if !(200..300).contains(&status) {
+        return Err(PingError::BadStatus(status));
+    }
+    Ok(PingResult {
+        status,
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Round-trip a real request, catching servers that accept connections but
+/// fail requests (e.g. returning 500s).
+#[tauri::command]
+pub(crate) fn ping_server() -> Result<PingResult, PingError> {
+    ping_health()
+}
+
+// ─── Server Log Level ──────────────────────────────────────────────────────
+
+const SERVER_LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+/// Env var the server reads its log level from at startup.
+const SERVER_LOG_LEVEL_ENV: &str = "HEXDECK_LOG_LEVEL";
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub(crate) enum LogLevelChange {
+    /// The running server switched levels immediately.
+    Applied,
+    /// The server (an older build) has no admin endpoint; the level was saved
+    /// to `server_env` and takes effect on the next restart.
+    RestartRequired,
+}
+
+/// The running server's log level, if it exposes one.
+#[tauri::command]
+pub(crate) async fn get_server_log_level() -> Result<Option<String>, String> {
+    let (status, body) = tauri::async_runtime::spawn_blocking(|| server_request("GET", "/admin/log-level", None))
+        .await
+        .map_err(|e| e.to_string())??;
+    if !(200..300).contains(&status) {
+        return Ok(None);
+    }
+    let value: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    Ok(value["level"].as_str().map(str::to_string))
+}
+
+/// Change the server's verbosity live through its admin endpoint, falling
+/// back to persisting the level in `server_env` for the next start.
+#[tauri::command]
+pub(crate) async fn set_server_log_level(level: String) -> Result<LogLevelChange, String> {
+    if !SERVER_LOG_LEVELS.contains(&level.as_str()) {
+        return Err(format!(
+            "Unknown log level: {level} (expected one of {})",
+            SERVER_LOG_LEVELS.join(", ")
+        ));
+    }
+
+    // Keep server_env in step either way, so a later restart keeps the level
+    let mut settings = load_settings();
+    settings
+        .server_env
+        .insert(SERVER_LOG_LEVEL_ENV.to_string(), level.clone());
+    save_settings(&settings)?;
+
+    let body = serde_json::json!({ "level": level }).to_string();
+    let response = tauri::async_runtime::spawn_blocking(move || server_request("POST", "/admin/log-level", Some(&body)))
+        .await
+        .map_err(|e| e.to_string())?;
+    match response {
+        Ok((status, _)) if (200..300).contains(&status) => Ok(LogLevelChange::Applied),
+        Ok((status, body)) if status != 404 => Err(format!("Server rejected log level ({status}): {body}")),
+        _ => Ok(LogLevelChange::RestartRequired),
+    }
+}
@@ -0,0 +1,935 @@
+//! Widget settings and the settings file.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+use crate::background::spawn_background;
+use crate::dashboard::parse_http_url;
+use crate::logs::{MAX_LOG_FILES, MIN_LOG_SIZE_BYTES};
+use crate::main_window::{MAIN_MIN_HEIGHT, MAIN_MIN_WIDTH};
+use crate::paths::{
+    bootstrap_dir, hexdeck_dir, invalidate_hexdeck_dir, settings_file, write_atomic,
+};
+use crate::profiles::{active_profile, active_profile_file};
+use crate::server::{
+    adopted_port, ensure_server_running, is_port_available, kill_server_on, reset_spawn_attempts,
+    resolve_server_binary, ServerSource, DEFAULT_SERVER_PORT,
+};
+use crate::shortcuts::{
+    active_shortcuts, handle_shortcut, DEFAULT_POPUP_SHORTCUT, DEFAULT_WIDGET_SHORTCUT,
+};
+use crate::status::MIN_STATUS_POLL_INTERVAL;
+use crate::tray::{
+    current_tray_color, parse_hex_color, refresh_menu_labels, set_tray_color, set_tray_tooltip,
+    with_tray_items,
+};
+use crate::widget::{
+    apply_widget_spaces, apply_widget_visibility, apply_window_stacking, set_window_alpha,
+    SHOW_WIDGET, WIDGET_DIMMED, WIDGET_VIBRANCY_MATERIALS, WINDOW_ALPHA_SETTINGS,
+    WINDOW_ALPHA_SUPPORTED,
+};
+use crate::widget_position::{WidgetPositionPct, MAX_POSITION_HISTORY, WIDGET_ANCHORS};
+
+/// How `open_dashboard` presents the dashboard.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DashboardOpenMode {
+    /// A tab in the default browser.
+    #[default]
+    Browser,
+    /// A chromeless Chromium `--app` window, when such a browser is installed.
+    AppWindow,
+}
+
+/// What clicking the widget does while the server is down.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WidgetDownAction {
+    #[default]
+    Reconnect,
+    OpenLogs,
+    Nothing,
+}
+
+/// Stacking of the widget relative to the popup when both are on screen.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WidgetStacking {
+    AbovePopup,
+    #[default]
+    BelowPopup,
+}
+
+/// Where the popup appears each time it opens, from the tray or a shortcut.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MainOpenPlacement {
+    #[default]
+    DockUnderTray,
+    /// Where it was when last hidden, e.g. after being dragged away.
+    RestoreLast,
+    Center,
+}
+
+/// What closing the popup or widget (Cmd+W, a close button) does.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CloseAction {
+    /// Hide the window; the app keeps running in the menu bar.
+    #[default]
+    Hide,
+    /// Quit the app, confirming first if `confirm_on_quit` is on.
+    Quit,
+}
+
+/// How much the tray tooltip says.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TrayTooltipDetail {
+    /// Just "Hexdeck".
+    #[default]
+    Minimal,
+    /// Server state, PID, uptime and port, refreshed by the status poller.
+    Detailed,
+}
+
+/// What the popup does when it loses focus.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum HideMode {
+    Never,
+    AfterDelay,
+    #[default]
+    Immediate,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct WidgetSettings {
+    pub(crate) show_widget: bool,
+    #[serde(default)]
+    pub(crate) has_seen_tooltip: bool,
+    #[serde(default)]
+    pub(crate) onboarding_v1_completed: bool,
+    /// Keep the widget on every macOS Space instead of only the one it was shown on.
+    #[serde(default)]
+    pub(crate) widget_visible_on_all_spaces: bool,
+    /// Float the widget over full-screen apps instead of yielding to them.
+    #[serde(default)]
+    pub(crate) widget_over_fullscreen: bool,
+    #[serde(default = "default_popup_shortcut")]
+    pub(crate) popup_shortcut: String,
+    #[serde(default = "default_widget_shortcut")]
+    pub(crate) widget_shortcut: String,
+    /// Host the server is probed on; may be a name that resolves to several addresses.
+    #[serde(default = "default_server_host")]
+    pub(crate) server_host: String,
+    /// Dashboard URL for a proxied or remote server, used verbatim; every
+    /// server request goes to its host/port, and no local server is spawned.
+    /// `None` means localhost.
+    #[serde(default)]
+    pub(crate) dashboard_base_url: Option<String>,
+    /// Named screen corner the widget is pinned to; re-resolved when the monitor changes.
+    #[serde(default)]
+    pub(crate) widget_anchor: Option<String>,
+    /// Percentage placement, re-resolved like an anchor; the two are exclusive.
+    #[serde(default)]
+    pub(crate) widget_position_pct: Option<WidgetPositionPct>,
+    /// Keep the widget clear of the menu bar and camera notch, even when the
+    /// menu bar auto-hides. macOS only.
+    #[serde(default = "default_true")]
+    pub(crate) respect_safe_area: bool,
+    /// Past widget positions kept for `undo_widget_position`; 0 disables.
+    #[serde(default = "default_widget_position_history")]
+    pub(crate) widget_position_history: usize,
+    /// Monitor name the widget is kept on; `None` lets it go anywhere.
+    #[serde(default)]
+    pub(crate) widget_monitor: Option<String>,
+    /// Briefly focus the widget when it's shown so macOS starts delivering
+    /// hover events to it; focus goes straight back to the previous app.
+    #[serde(default = "default_widget_focus_flash_on_show")]
+    pub(crate) widget_focus_flash_on_show: bool,
+    /// Drop a shadow behind the widget (macOS and Windows).
+    #[serde(default = "default_true")]
+    pub(crate) widget_shadow: bool,
+    /// Opacity of the widget while in use (0.1–1.0). macOS and Linux only;
+    /// see `WINDOW_ALPHA_SUPPORTED`.
+    #[serde(default = "default_widget_opacity")]
+    pub(crate) widget_opacity: f64,
+    /// Dim the widget after `widget_idle_timeout_ms` without interaction.
+    /// The widget never ignores the mouse, so hovering it always brightens it
+    /// again; there is no click-through mode to exempt.
+    #[serde(default)]
+    pub(crate) widget_idle_dim: bool,
+    #[serde(default = "default_widget_idle_timeout_ms")]
+    pub(crate) widget_idle_timeout_ms: u64,
+    /// Popup size in logical pixels; `None` keeps the size from tauri.conf.json.
+    #[serde(default)]
+    pub(crate) main_width: Option<f64>,
+    #[serde(default)]
+    pub(crate) main_height: Option<f64>,
+    /// macOS vibrancy material behind the widget (e.g. "hud", "sidebar").
+    #[serde(default)]
+    pub(crate) widget_vibrancy: Option<String>,
+    #[serde(default)]
+    pub(crate) main_hide_mode: HideMode,
+    /// Give the popup a title bar ("detached" mode). A decorated popup
+    /// behaves like a normal window and doesn't hide on focus loss.
+    #[serde(default)]
+    pub(crate) main_decorations: bool,
+    /// Hide the widget when another app takes focus, like the popup. It comes
+    /// back via the shortcut or menu.
+    #[serde(default)]
+    pub(crate) hide_widget_on_focus_loss: bool,
+    /// Grace period before hiding in `AfterDelay` mode.
+    #[serde(default = "default_main_hide_delay_ms")]
+    pub(crate) main_hide_delay_ms: u64,
+    /// `false` runs in widget-only mode with no menu bar icon.
+    #[serde(default = "default_true")]
+    pub(crate) show_tray_icon: bool,
+    /// Optional global shortcut that quits the app.
+    #[serde(default)]
+    pub(crate) quit_shortcut: Option<String>,
+    /// Relocated state directory (e.g. on a synced drive); `None` uses the default.
+    #[serde(default)]
+    pub(crate) data_dir: Option<String>,
+    /// Vertical gap between the tray icon and the popup, in physical pixels.
+    #[serde(default = "default_popup_tray_gap")]
+    pub(crate) popup_tray_gap: f64,
+    /// Horizontal nudge of the popup from centered under the tray icon, in
+    /// physical pixels (negative moves left).
+    #[serde(default)]
+    pub(crate) popup_offset_x: f64,
+    /// Show the popup once the server is up at launch (kiosk/unattended use).
+    #[serde(default)]
+    pub(crate) show_main_on_server_ready: bool,
+    /// Play the system sound with notifications; `false` still shows them, silently.
+    #[serde(default = "default_true")]
+    pub(crate) notification_sound: bool,
+    /// Opening the popup from the tray also brings up the widget (closing the
+    /// popup leaves the widget alone).
+    #[serde(default)]
+    pub(crate) left_click_shows_widget: bool,
+    /// Tray clicks closer together than this collapse into one toggle.
+    #[serde(default = "default_tray_click_debounce_ms")]
+    pub(crate) tray_click_debounce_ms: u64,
+    /// Status poll cadence: `min_poll_interval_ms` right after a change,
+    /// doubling while stable up to `max_poll_interval_ms`.
+    #[serde(default = "default_min_poll_interval_ms")]
+    pub(crate) min_poll_interval_ms: u64,
+    #[serde(default = "default_max_poll_interval_ms")]
+    pub(crate) max_poll_interval_ms: u64,
+    /// Motion effects such as the popup sliding in from the tray. Also skipped
+    /// when the system asks for reduced motion.
+    #[serde(default = "default_true")]
+    pub(crate) animations: bool,
+    #[serde(default)]
+    pub(crate) dashboard_open_mode: DashboardOpenMode,
+    #[serde(default)]
+    pub(crate) tray_tooltip_detail: TrayTooltipDetail,
+    #[serde(default)]
+    pub(crate) widget_stacking: WidgetStacking,
+    #[serde(default)]
+    pub(crate) widget_down_action: WidgetDownAction,
+    #[serde(default)]
+    pub(crate) main_open_placement: MainOpenPlacement,
+    #[serde(default)]
+    pub(crate) close_action: CloseAction,
+    /// Focus an already-open dashboard tab instead of opening a new one.
+    #[serde(default)]
+    pub(crate) dashboard_reuse_tab: bool,
+    /// Spawns tried (with backoff) before giving up until the server is next
+    /// seen up or the user restarts it. 0 means no limit: keep retrying at
+    /// the maximum backoff.
+    #[serde(default = "default_max_spawn_attempts")]
+    pub(crate) max_spawn_attempts_per_session: u64,
+    /// A log is rotated once it grows past this many bytes.
+    #[serde(default = "default_log_max_size_bytes")]
+    pub(crate) log_max_size_bytes: u64,
+    /// Rotated generations kept (`server.log.1` …); older ones are deleted.
+    #[serde(default = "default_log_max_files")]
+    pub(crate) log_max_files: u32,
+    /// A live PID whose server still isn't reachable is treated as a recycled
+    /// PID (and its file cleaned up) once the file is older than this.
+    #[serde(default = "default_pid_staleness_secs")]
+    pub(crate) pid_staleness_secs: u64,
+    /// Pause before spawning the server after an update relaunch, giving the
+    /// old server time to exit.
+    #[serde(default = "default_relaunch_settle_ms")]
+    pub(crate) relaunch_settle_ms: u64,
+    /// Start the server shortly after launch so the first dashboard open is
+    /// instant. When off it starts on first demand (`ensure_server`,
+    /// `prepare_dashboard`) and the watchdog only keeps it up after that.
+    #[serde(default = "default_true")]
+    pub(crate) prestart_server: bool,
+    /// Wait after launch before prestarting, to stay out of login's way.
+    #[serde(default)]
+    pub(crate) prestart_delay_ms: u64,
+    /// Enables developer-only commands such as `cycle_tray_color`.
+    #[serde(default)]
+    pub(crate) debug_mode: bool,
+    /// Server binary to run instead of the bundled one (takes precedence over
+    /// `$HEXDECK_SERVER_BIN`).
+    #[serde(default)]
+    pub(crate) server_binary: Option<String>,
+    /// Working directory for the spawned server; `None` uses the state
+    /// directory, so relative paths don't depend on how the app was launched.
+    #[serde(default)]
+    pub(crate) server_cwd: Option<String>,
+    /// Check the server binary against its `.sha256` file before each spawn
+    /// and refuse to run it on a mismatch.
+    #[serde(default)]
+    pub(crate) verify_server_binary: bool,
+    /// Branding accent (`#rrggbb`): tints the idle tray icon and is sent to
+    /// the widget. `None` keeps the stock grey icon.
+    #[serde(default)]
+    pub(crate) accent_color: Option<String>,
+    /// Show the idle tray icon as a macOS template image, tinted by the menu
+    /// bar to match light/dark. Status colors still show in color.
+    #[serde(default)]
+    pub(crate) tray_template: bool,
+    /// Port our own server is spawned on.
+    #[serde(default = "default_server_port")]
+    pub(crate) server_port: u16,
+    /// Extra environment variables for the spawned server (e.g. `RUST_LOG`).
+    #[serde(default)]
+    pub(crate) server_env: HashMap<String, String>,
+    /// Start the server from an empty environment instead of inheriting ours.
+    #[serde(default)]
+    pub(crate) server_env_clear: bool,
+    /// The server runs under launchd/systemd: never spawn or kill it, and
+    /// route restart/stop through the service commands below.
+    #[serde(default)]
+    pub(crate) server_managed_externally: bool,
+    /// Ask before quitting from the tray, shortcut or `quit_app`.
+    #[serde(default)]
+    pub(crate) confirm_on_quit: bool,
+    /// Argv run by `restart_server` when managed externally, e.g.
+    /// `["launchctl", "kickstart", "-k", "gui/501/dev.hexdeck.server"]`.
+    #[serde(default)]
+    pub(crate) service_restart_command: Vec<String>,
+    /// Argv run by `stop_server` when managed externally, e.g.
+    /// `["systemctl", "--user", "stop", "hexdeck"]`.
+    #[serde(default)]
+    pub(crate) service_stop_command: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_popup_shortcut() -> String {
+    DEFAULT_POPUP_SHORTCUT.to_string()
+}
+
+fn default_widget_shortcut() -> String {
+    DEFAULT_WIDGET_SHORTCUT.to_string()
+}
+
+fn default_server_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_widget_focus_flash_on_show() -> bool {
+    cfg!(target_os = "macos")
+}
+
+fn default_widget_opacity() -> f64 {
+    1.0
+}
+
+fn default_widget_idle_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_main_hide_delay_ms() -> u64 {
+    1_000
+}
+
+fn default_server_port() -> u16 {
+    DEFAULT_SERVER_PORT
+}
+
+fn default_pid_staleness_secs() -> u64 {
+    120
+}
+
+fn default_log_max_size_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+fn default_log_max_files() -> u32 {
+    3
+}
+
+fn default_tray_click_debounce_ms() -> u64 {
+    300
+}
+
+fn default_widget_position_history() -> usize {
+    5
+}
+
+fn default_relaunch_settle_ms() -> u64 {
+    2_000
+}
+
+fn default_max_spawn_attempts() -> u64 {
+    5
+}
+
+fn default_min_poll_interval_ms() -> u64 {
+    1_000
+}
+
+fn default_max_poll_interval_ms() -> u64 {
+    15_000
+}
+
+fn default_popup_tray_gap() -> f64 {
+    4.0
+}
+
+impl Default for WidgetSettings {
+    fn default() -> Self {
+        WidgetSettings {
+            show_widget: true,
+            has_seen_tooltip: false,
+            onboarding_v1_completed: false,
+            widget_visible_on_all_spaces: false,
+            widget_over_fullscreen: false,
+            popup_shortcut: default_popup_shortcut(),
+            widget_shortcut: default_widget_shortcut(),
+            server_host: default_server_host(),
+            dashboard_base_url: None,
+            widget_anchor: None,
+            widget_position_pct: None,
+            respect_safe_area: true,
+            widget_position_history: default_widget_position_history(),
+            widget_monitor: None,
+            widget_focus_flash_on_show: default_widget_focus_flash_on_show(),
+            widget_shadow: true,
+            widget_opacity: default_widget_opacity(),
+            widget_idle_dim: false,
+            widget_idle_timeout_ms: default_widget_idle_timeout_ms(),
+            main_width: None,
+            main_height: None,
+            widget_vibrancy: None,
+            main_hide_mode: HideMode::default(),
+            main_decorations: false,
+            hide_widget_on_focus_loss: false,
+            main_hide_delay_ms: default_main_hide_delay_ms(),
+            show_tray_icon: true,
+            quit_shortcut: None,
+            data_dir: None,
+            popup_tray_gap: default_popup_tray_gap(),
+            popup_offset_x: 0.0,
+            show_main_on_server_ready: false,
+            notification_sound: true,
+            left_click_shows_widget: false,
+            tray_click_debounce_ms: default_tray_click_debounce_ms(),
+            min_poll_interval_ms: default_min_poll_interval_ms(),
+            max_poll_interval_ms: default_max_poll_interval_ms(),
+            animations: true,
+            tray_tooltip_detail: TrayTooltipDetail::default(),
+            widget_stacking: WidgetStacking::default(),
+            widget_down_action: WidgetDownAction::default(),
+            main_open_placement: MainOpenPlacement::default(),
+            close_action: CloseAction::default(),
+            dashboard_open_mode: DashboardOpenMode::default(),
+            dashboard_reuse_tab: false,
+            max_spawn_attempts_per_session: default_max_spawn_attempts(),
+            log_max_size_bytes: default_log_max_size_bytes(),
+            log_max_files: default_log_max_files(),
+            pid_staleness_secs: default_pid_staleness_secs(),
+            relaunch_settle_ms: default_relaunch_settle_ms(),
+            prestart_server: true,
+            prestart_delay_ms: 0,
+            debug_mode: false,
+            server_binary: None,
+            server_cwd: None,
+            verify_server_binary: false,
+            accent_color: None,
+            tray_template: false,
+            server_port: default_server_port(),
+            server_env: HashMap::new(),
+            server_env_clear: false,
+            server_managed_externally: false,
+            confirm_on_quit: false,
+            service_restart_command: Vec::new(),
+            service_stop_command: Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn load_settings() -> WidgetSettings {
+    let Some(path) = settings_file() else {
+        return WidgetSettings::default();
+    };
+    let Ok(data) = fs::read_to_string(path) else {
+        return WidgetSettings::default();
+    };
+    serde_json::from_str(&data).unwrap_or_default()
+}
+
+/// Settings as last loaded or saved. Everything that only reads settings goes
+/// through `cached_settings`, so probes, pollers and clicks don't re-parse the
+/// file; read-modify-write paths use `load_settings`, which picks up hand
+/// edits before saving over them. `save_settings` keeps the cache current and
+/// switching profile clears it.
+static SETTINGS_CACHE: Mutex<Option<WidgetSettings>> = Mutex::new(None);
+
+pub(crate) fn cached_settings() -> WidgetSettings {
+    let Ok(mut cache) = SETTINGS_CACHE.lock() else {
+        return load_settings();
+    };
+    cache.get_or_insert_with(load_settings).clone()
+}
+
+pub(crate) fn invalidate_settings_cache() {
+    if let Ok(mut cache) = SETTINGS_CACHE.lock() {
+        *cache = None;
+    }
+}
+
+pub(crate) fn save_settings(settings: &WidgetSettings) -> Result<(), String> {
+    let path = settings_file().ok_or("Cannot resolve home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(settings).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())?;
+    if let Ok(mut cache) = SETTINGS_CACHE.lock() {
+        *cache = Some(settings.clone());
+    }
+    invalidate_hexdeck_dir();
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn load_has_seen_tooltip() -> bool {
+    cached_settings().has_seen_tooltip
+}
+
+#[tauri::command]
+pub(crate) fn save_has_seen_tooltip() -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.has_seen_tooltip = true;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub(crate) fn load_has_completed_onboarding() -> bool {
+    cached_settings().onboarding_v1_completed
+}
+
+#[tauri::command]
+pub(crate) fn save_has_completed_onboarding() -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.onboarding_v1_completed = true;
+    save_settings(&settings)
+}
+
+// ─── Settings Schema ───────────────────────────────────────────────────────
+
+/// Describes one `WidgetSettings` field for a generic settings form.
+#[derive(Serialize)]
+pub(crate) struct SettingField {
+    pub(crate) name: String,
+    /// JSON type: "boolean", "integer", "number", "string" or "object".
+    pub(crate) kind: &'static str,
+    nullable: bool,
+    pub(crate) default: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Vec<&'static str>>,
+}
+
+/// JSON types of fields whose default is `None`, which can't be inferred
+/// from the default value.
+const NULLABLE_SETTING_KINDS: [(&str, &str); 12] = [
+    ("widget_anchor", "string"),
+    ("widget_position_pct", "object"),
+    ("widget_monitor", "string"),
+    ("main_width", "number"),
+    ("main_height", "number"),
+    ("widget_vibrancy", "string"),
+    ("quit_shortcut", "string"),
+    ("data_dir", "string"),
+    ("server_binary", "string"),
+    ("server_cwd", "string"),
+    ("accent_color", "string"),
+    ("dashboard_base_url", "string"),
+];
+
+fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(n) if n.is_u64() || n.is_i64() => "integer",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        _ => "object",
+    }
+}
+
+/// Field list for `WidgetSettings`. Names, types and defaults come from
+/// serializing the defaults, so new fields appear automatically; ranges and
+/// choices mirror the validation in the matching `set_*` commands.
+#[tauri::command]
+pub(crate) fn get_settings_schema() -> Vec<SettingField> {
+    let Ok(serde_json::Value::Object(defaults)) = serde_json::to_value(WidgetSettings::default()) else {
+        return Vec::new();
+    };
+    defaults
+        .into_iter()
+        .filter(|(name, _)| WINDOW_ALPHA_SUPPORTED || !WINDOW_ALPHA_SETTINGS.contains(&name.as_str()))
+        .map(|(name, default)| {
+            let nullable = default.is_null();
+            let kind = if nullable {
+                NULLABLE_SETTING_KINDS
+                    .iter()
+                    .find(|(field, _)| *field == name)
+                    .map_or("string", |(_, kind)| *kind)
+            } else {
+                json_kind(&default)
+            };
+            let (min, max, options) = match name.as_str() {
+                "widget_opacity" => (Some(0.1), Some(1.0), None),
+                "server_port" => (Some(1.0), Some(u16::MAX as f64), None),
+                "log_max_size_bytes" => (Some(MIN_LOG_SIZE_BYTES as f64), None, None),
+                "log_max_files" => (Some(1.0), Some(MAX_LOG_FILES as f64), None),
+                "widget_position_history" => (Some(0.0), Some(MAX_POSITION_HISTORY as f64), None),
+                "main_width" => (Some(MAIN_MIN_WIDTH), None, None),
+                "main_height" => (Some(MAIN_MIN_HEIGHT), None, None),
+                "min_poll_interval_ms" | "max_poll_interval_ms" => {
+                    (Some(MIN_STATUS_POLL_INTERVAL.as_millis() as f64), None, None)
+                }
+                "widget_anchor" => (None, None, Some(WIDGET_ANCHORS.to_vec())),
+                "widget_vibrancy" => (None, None, Some(WIDGET_VIBRANCY_MATERIALS.to_vec())),
+                "main_hide_mode" => (None, None, Some(vec!["never", "after_delay", "immediate"])),
+                "dashboard_open_mode" => (None, None, Some(vec!["browser", "app_window"])),
+                "tray_tooltip_detail" => (None, None, Some(vec!["minimal", "detailed"])),
+                "widget_stacking" => (None, None, Some(vec!["above_popup", "below_popup"])),
+                "widget_down_action" => (None, None, Some(vec!["reconnect", "open_logs", "nothing"])),
+                "main_open_placement" => (None, None, Some(vec!["dock_under_tray", "restore_last", "center"])),
+                "close_action" => (None, None, Some(vec!["hide", "quit"])),
+                _ => (None, None, None),
+            };
+            SettingField {
+                name,
+                kind,
+                nullable,
+                default,
+                min,
+                max,
+                options,
+            }
+        })
+        .collect()
+}
+
+// ─── Effective Config ──────────────────────────────────────────────────────
+
+/// Where an effective configuration value came from, lowest precedence first.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ConfigSource {
+    Default,
+    File,
+    Env,
+    /// Set at runtime by a command (e.g. `adopt_server`) and not persisted.
+    Runtime,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ConfigEntry {
+    pub(crate) value: serde_json::Value,
+    pub(crate) source: ConfigSource,
+}
+
+fn config_entry(value: impl Serialize, source: ConfigSource) -> ConfigEntry {
+    ConfigEntry {
+        value: serde_json::to_value(value).unwrap_or(serde_json::Value::Null),
+        source,
+    }
+}
+
+/// Keys present in the settings file, if it parses; a file that doesn't
+/// parse is ignored by `load_settings`, so nothing in it is in effect.
+fn settings_file_keys() -> Vec<String> {
+    let Some(data) = settings_file().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    if serde_json::from_str::<WidgetSettings>(&data).is_err() {
+        return Vec::new();
+    }
+    match serde_json::from_str::<serde_json::Value>(&data) {
+        Ok(serde_json::Value::Object(map)) => map.into_iter().map(|(key, _)| key).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Every setting as currently resolved, plus the derived paths and port, each
+/// tagged with where it came from. Precedence is runtime > env > file > default.
+#[tauri::command]
+pub(crate) fn get_effective_config(app: tauri::AppHandle) -> BTreeMap<String, ConfigEntry> {
+    let file_keys = settings_file_keys();
+    let mut config = BTreeMap::new();
+
+    if let Ok(serde_json::Value::Object(settings)) = serde_json::to_value(load_settings()) {
+        for (key, value) in settings {
+            let source = if file_keys.contains(&key) {
+                ConfigSource::File
+            } else {
+                ConfigSource::Default
+            };
+            config.insert(key, ConfigEntry { value, source });
+        }
+    }
+
+    let home_source = if std::env::var_os("HEXDECK_HOME").is_some_and(|v| !v.is_empty()) {
+        ConfigSource::Env
+    } else {
+        ConfigSource::Default
+    };
+    config.insert("hexdeck_home".into(), config_entry(bootstrap_dir(), home_source));
+
+    let profile_source = if active_profile_file().is_some_and(|path| path.exists()) {
+        ConfigSource::File
+    } else {
+        ConfigSource::Default
+    };
+    config.insert("profile".into(), config_entry(active_profile(), profile_source));
+
+    let state_source = if file_keys.iter().any(|key| key == "data_dir") {
+        ConfigSource::File
+    } else {
+        home_source
+    };
+    config.insert("state_dir".into(), config_entry(hexdeck_dir(), state_source));
+
+    if let Ok((path, source)) = resolve_server_binary(&app) {
+        let source = match source {
+            ServerSource::Settings => ConfigSource::File,
+            ServerSource::Env => ConfigSource::Env,
+            ServerSource::Resource => ConfigSource::Default,
+        };
+        config.insert("server_binary_path".into(), config_entry(path, source));
+    }
+
+    if let Some(port) = adopted_port() {
+        config.insert("server_port".into(), config_entry(port, ConfigSource::Runtime));
+    }
+
+    config
+}
+
+// ─── Settings Transaction ──────────────────────────────────────────────────
+
+/// Why `apply_settings_transaction` rejected or rolled back an update.
+#[derive(Serialize)]
+pub(crate) struct SettingsFieldError {
+    field: &'static str,
+    pub(crate) message: String,
+}
+
+fn field_error(field: &'static str, message: impl Into<String>) -> SettingsFieldError {
+    SettingsFieldError {
+        field,
+        message: message.into(),
+    }
+}
+
+/// The shortcut fields of `settings`, in registration order.
+fn shortcut_fields(settings: &WidgetSettings) -> Vec<(&'static str, &str)> {
+    let mut fields = vec![
+        ("popup_shortcut", settings.popup_shortcut.as_str()),
+        ("widget_shortcut", settings.widget_shortcut.as_str()),
+    ];
+    if let Some(combo) = settings.quit_shortcut.as_deref() {
+        fields.push(("quit_shortcut", combo));
+    }
+    fields
+}
+
+/// Checks that need no side effects: ranges, parsing, a free port.
+fn validate_settings(new: &WidgetSettings, previous: &WidgetSettings) -> Result<(), SettingsFieldError> {
+    if !(0.1..=1.0).contains(&new.widget_opacity) {
+        return Err(field_error("widget_opacity", "Opacity must be between 0.1 and 1.0"));
+    }
+    if !WINDOW_ALPHA_SUPPORTED {
+        if new.widget_opacity != default_widget_opacity() {
+            return Err(field_error("widget_opacity", "Widget opacity isn't supported on this platform"));
+        }
+        if new.widget_idle_dim {
+            return Err(field_error("widget_idle_dim", "Idle dimming isn't supported on this platform"));
+        }
+    }
+    let mut seen: Vec<Shortcut> = Vec::new();
+    for (field, combo) in shortcut_fields(new) {
+        let shortcut: Shortcut = combo
+            .parse()
+            .map_err(|e| field_error(field, format!("Invalid shortcut: {e}")))?;
+        if seen.contains(&shortcut) {
+            return Err(field_error(field, format!("{combo} is already used by another action")));
+        }
+        seen.push(shortcut);
+    }
+    if let Some(hex) = new.accent_color.as_deref() {
+        parse_hex_color(hex).map_err(|e| field_error("accent_color", e))?;
+    }
+    if let Some(url) = new.dashboard_base_url.as_deref() {
+        parse_http_url(url).map_err(|e| field_error("dashboard_base_url", e))?;
+    }
+    if let Some(cwd) = new.server_cwd.as_deref().filter(|p| !p.is_empty()) {
+        if !Path::new(cwd).is_absolute() {
+            return Err(field_error("server_cwd", "Server working directory must be an absolute path"));
+        }
+    }
+    if new.server_port == 0 {
+        return Err(field_error("server_port", "Invalid port: 0"));
+    }
+    if new.server_port != previous.server_port && !is_port_available(new.server_port) {
+        return Err(field_error("server_port", format!("Port {} is already in use", new.server_port)));
+    }
+    Ok(())
+}
+
+/// Which groups of side effects `apply_settings_effects` has started, so a
+/// rollback undoes only those.
+#[derive(Default)]
+struct AppliedEffects {
+    shortcuts: bool,
+    windows: bool,
+    tray: bool,
+    server: bool,
+}
+
+/// Register shortcuts one by one so a conflict names its field.
+fn apply_shortcut_effects(app: &tauri::AppHandle, settings: &WidgetSettings) -> Result<(), SettingsFieldError> {
+    let global_shortcut = app.global_shortcut();
+    global_shortcut
+        .unregister_all()
+        .map_err(|e| field_error("popup_shortcut", e.to_string()))?;
+    for shortcut in active_shortcuts() {
+        global_shortcut.on_shortcut(shortcut, handle_shortcut).map_err(|e| {
+            let field = shortcut_fields(settings)
+                .into_iter()
+                .find(|(_, combo)| combo.parse::<Shortcut>().ok() == Some(shortcut))
+                .map_or("quit_shortcut", |(field, _)| field);
+            field_error(field, e.to_string())
+        })?;
+    }
+    refresh_menu_labels(app);
+    Ok(())
+}
+
+fn apply_window_effects(app: &tauri::AppHandle, settings: &WidgetSettings) {
+    SHOW_WIDGET.store(settings.show_widget, Ordering::SeqCst);
+    with_tray_items(app, |items| {
+        let _ = items.show_widget.set_checked(settings.show_widget);
+    });
+    apply_widget_visibility(app, settings.show_widget);
+    if let Some(widget) = app.get_webview_window("widget") {
+        WIDGET_DIMMED.store(false, Ordering::SeqCst);
+        set_window_alpha(&widget, settings.widget_opacity);
+        apply_widget_spaces(&widget, settings);
+    }
+    apply_window_stacking(app);
+}
+
+fn apply_tray_effects(app: &tauri::AppHandle) -> Result<(), SettingsFieldError> {
+    set_tray_tooltip(app, None);
+    if current_tray_color() == "grey" {
+        set_tray_color(app, "grey").map_err(|e| field_error("accent_color", e))?;
+    }
+    Ok(())
+}
+
+/// Move the server from `from_port` to `settings.server_port`, off the
+/// calling thread since killing waits for the old server to exit.
+fn apply_server_effects(app: &tauri::AppHandle, settings: &WidgetSettings, from_port: u16) {
+    if settings.server_port == from_port {
+        return;
+    }
+    let app = app.clone();
+    spawn_background("server-port-change", move || {
+        kill_server_on(from_port);
+        reset_spawn_attempts();
+        ensure_server_running(&app);
+    });
+}
+
+/// Bring the running app in line with `settings` (already saved), recording
+/// in `applied` each group of effects as it starts.
+fn apply_settings_effects(
+    app: &tauri::AppHandle,
+    settings: &WidgetSettings,
+    previous: &WidgetSettings,
+    applied: &mut AppliedEffects,
+) -> Result<(), SettingsFieldError> {
+    applied.shortcuts = true;
+    apply_shortcut_effects(app, settings)?;
+    applied.windows = true;
+    apply_window_effects(app, settings);
+    applied.tray = true;
+    apply_tray_effects(app)?;
+    applied.server = true;
+    apply_server_effects(app, settings, previous.server_port);
+    Ok(())
+}
+
+/// Re-apply `previous` for just the effects that ran, so e.g. a shortcut
+/// conflict doesn't restart a server whose port change never happened.
+fn revert_settings_effects(
+    app: &tauri::AppHandle,
+    previous: &WidgetSettings,
+    failed: &WidgetSettings,
+    applied: &AppliedEffects,
+) {
+    if applied.shortcuts {
+        if let Err(e) = apply_shortcut_effects(app, previous) {
+            menubar_log!("failed to restore shortcuts: {}", e.message);
+        }
+    }
+    if applied.windows {
+        apply_window_effects(app, previous);
+    }
+    if applied.tray {
+        let _ = apply_tray_effects(app);
+    }
+    if applied.server {
+        apply_server_effects(app, previous, failed.server_port);
+    }
+}
+
+/// Apply several settings at once: validate everything, save, then apply the
+/// side effects. If any step fails the previous settings are restored, the
+/// effects already applied are reverted, and the failing field is reported.
+/// Prefer this over saving fields one at a time from the settings screen.
+#[tauri::command]
+pub(crate) async fn apply_settings_transaction(app: tauri::AppHandle, new: WidgetSettings) -> Result<(), SettingsFieldError> {
+    let previous = load_settings();
+    validate_settings(&new, &previous)?;
+    save_settings(&new).map_err(|e| field_error("settings", e))?;
+
+    let mut applied = AppliedEffects::default();
+    let Err(error) = apply_settings_effects(&app, &new, &previous, &mut applied) else {
+        return Ok(());
+    };
+    menubar_log!("settings update failed on {}: {}; rolling back", error.field, error.message);
+    if let Err(e) = save_settings(&previous) {
+        menubar_log!("failed to restore settings: {e}");
+    }
+    revert_settings_effects(&app, &previous, &new, &applied);
+    Err(error)
+}
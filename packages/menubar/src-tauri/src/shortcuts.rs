@@ -0,0 +1,236 @@
+//! Global shortcuts.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::Emitter;
+use tauri_plugin_global_shortcut::{
+    GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState,
+};
+
+use crate::background::{sleep_unless_shutdown, spawn_background};
+use crate::main_window::toggle_main_window_from_shortcut;
+use crate::notifications::notify;
+use crate::request_quit;
+use crate::settings::{cached_settings, load_settings, save_settings};
+use crate::tray::refresh_menu_labels;
+use crate::widget::toggle_widget;
+
+/// Check whether `combo` can be registered as a global shortcut right now
+/// (i.e. no other app owns it). Registers and immediately unregisters it;
+/// shortcuts we already hold are reported as available and left untouched.
+#[tauri::command]
+pub(crate) fn test_shortcut(app: tauri::AppHandle, combo: String) -> bool {
+    let Ok(shortcut) = combo.parse::<Shortcut>() else {
+        return false;
+    };
+    let global_shortcut = app.global_shortcut();
+    if global_shortcut.is_registered(shortcut) {
+        return true;
+    }
+    if global_shortcut.register(shortcut).is_err() {
+        return false;
+    }
+    let _ = global_shortcut.unregister(shortcut);
+    true
+}
+
+/// Whether `combo` is currently registered by this app. Unlike `test_shortcut`
+/// this has no side effects; invalid combos report `false`.
+#[tauri::command]
+pub(crate) fn is_shortcut_registered(app: tauri::AppHandle, combo: String) -> bool {
+    combo
+        .parse::<Shortcut>()
+        .is_ok_and(|shortcut| app.global_shortcut().is_registered(shortcut))
+}
+
+pub(crate) const DEFAULT_POPUP_SHORTCUT: &str = "Cmd+Ctrl+H";
+pub(crate) const DEFAULT_WIDGET_SHORTCUT: &str = "Cmd+Ctrl+K";
+/// Quit shortcut forced on in widget-only mode when none is configured, so
+/// there is always a way out without the tray menu. Not Cmd+Ctrl+Q, which
+/// macOS reserves for Lock Screen.
+pub(crate) const FALLBACK_QUIT_SHORTCUT: &str = "Cmd+Ctrl+X";
+
+fn parse_shortcut_or_default(combo: &str, default: &str) -> Shortcut {
+    combo
+        .parse()
+        .unwrap_or_else(|_| default.parse().expect("default shortcut is valid"))
+}
+
+pub(crate) fn popup_shortcut() -> Shortcut {
+    parse_shortcut_or_default(&cached_settings().popup_shortcut, DEFAULT_POPUP_SHORTCUT)
+}
+
+pub(crate) fn widget_shortcut() -> Shortcut {
+    parse_shortcut_or_default(&cached_settings().widget_shortcut, DEFAULT_WIDGET_SHORTCUT)
+}
+
+fn quit_shortcut() -> Option<Shortcut> {
+    let settings = cached_settings();
+    match settings.quit_shortcut.and_then(|combo| combo.parse().ok()) {
+        Some(shortcut) => Some(shortcut),
+        None if !settings.show_tray_icon => FALLBACK_QUIT_SHORTCUT.parse().ok(),
+        None => None,
+    }
+}
+
+/// All shortcuts that should be registered right now.
+pub(crate) fn active_shortcuts() -> Vec<Shortcut> {
+    let mut shortcuts = vec![popup_shortcut(), widget_shortcut()];
+    shortcuts.extend(quit_shortcut());
+    shortcuts
+}
+
+/// Human-readable form of a shortcut for menu hints, e.g. `Cmd+Ctrl+K`.
+pub(crate) fn format_shortcut(shortcut: &Shortcut) -> String {
+    let mut parts = Vec::new();
+    for (modifier, label) in [
+        (Modifiers::SUPER, "Cmd"),
+        (Modifiers::CONTROL, "Ctrl"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+    ] {
+        if shortcut.mods.contains(modifier) {
+            parts.push(label.to_string());
+        }
+    }
+    let key = format!("{:?}", shortcut.key);
+    let key = key
+        .strip_prefix("Key")
+        .or_else(|| key.strip_prefix("Digit"))
+        .unwrap_or(&key)
+        .to_string();
+    parts.push(key);
+    parts.join("+")
+}
+
+/// Replace all registered global shortcuts with the currently configured ones.
+/// Each is registered on its own, so one that's taken doesn't cost the rest;
+/// the error lists every shortcut that failed.
+pub(crate) fn register_shortcuts(app: &tauri::AppHandle) -> Result<(), String> {
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all().map_err(|e| e.to_string())?;
+    let failures: Vec<String> = active_shortcuts()
+        .into_iter()
+        .filter_map(|shortcut| {
+            global_shortcut
+                .on_shortcut(shortcut, handle_shortcut)
+                .err()
+                .map(|e| format!("{}: {e}", format_shortcut(&shortcut)))
+        })
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+const SHORTCUT_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+/// Set while a retry loop is running, so repeated failures don't stack loops.
+static SHORTCUT_RETRY_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Register shortcuts at startup. On macOS first launch this fails until the
+/// user grants input permissions, so instead of giving up we tell them and
+/// keep retrying in the background until it succeeds.
+pub(crate) fn register_shortcuts_or_retry(app: &tauri::AppHandle) {
+    let Err(e) = register_shortcuts(app) else {
+        return;
+    };
+    menubar_log!("failed to register global shortcuts: {e}");
+    // Desktop notifications can't carry actions, so the UI offers the
+    // "Open Settings" button (via open_permission_settings) off this event.
+    let _ = app.emit("shortcut-registration-failed", &e);
+    let _ = notify(
+        app,
+        "Hexdeck shortcuts are unavailable",
+        "Allow Hexdeck under System Settings → Privacy & Security → Accessibility. \
+         Shortcuts will start working once permission is granted.",
+    );
+    if SHORTCUT_RETRY_ACTIVE.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    spawn_background("shortcut-retry", move || {
+        while sleep_unless_shutdown(SHORTCUT_RETRY_INTERVAL) {
+            if register_shortcuts(&app).is_ok() {
+                break;
+            }
+        }
+        SHORTCUT_RETRY_ACTIVE.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Manual retry for when shortcuts failed to register (e.g. right after
+/// granting permission).
+#[tauri::command]
+pub(crate) fn reregister_shortcuts(app: tauri::AppHandle) -> Result<(), String> {
+    register_shortcuts(&app)
+}
+
+/// Open the System Settings privacy pane that gates global shortcuts.
+/// `pane` is "accessibility" (default) or "input-monitoring". Other platforms
+/// need no such permission, so this is a no-op there.
+#[tauri::command]
+pub(crate) fn open_permission_settings(pane: Option<String>) -> Result<(), String> {
+    let anchor = match pane.as_deref().unwrap_or("accessibility") {
+        "accessibility" => "Privacy_Accessibility",
+        "input-monitoring" => "Privacy_ListenEvent",
+        other => return Err(format!("Unknown permission pane: {other}")),
+    };
+    #[cfg(target_os = "macos")]
+    std::process::Command::new("open")
+        .arg(format!(
+            "x-apple.systempreferences:com.apple.preference.security?{anchor}"
+        ))
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    #[cfg(not(target_os = "macos"))]
+    let _ = anchor;
+    Ok(())
+}
+
+pub(crate) fn handle_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    if *shortcut == popup_shortcut() {
+        toggle_main_window_from_shortcut(app);
+    } else if *shortcut == widget_shortcut() {
+        toggle_widget(app);
+    } else if Some(*shortcut) == quit_shortcut() {
+        request_quit(app);
+    }
+}
+
+/// Rebind the `popup`, `widget` or `quit` shortcut. The new combo is registered
+/// before the old one is released, so a conflict leaves the current binding intact.
+#[tauri::command]
+pub(crate) fn set_shortcut(app: tauri::AppHandle, action: String, combo: String) -> Result<(), String> {
+    let shortcut: Shortcut = combo.parse().map_err(|e| format!("Invalid shortcut: {e}"))?;
+    let previous = match action.as_str() {
+        "popup" => Some(popup_shortcut()),
+        "widget" => Some(widget_shortcut()),
+        "quit" => quit_shortcut(),
+        _ => return Err(format!("Unknown shortcut action: {action}")),
+    };
+
+    if previous != Some(shortcut) {
+        let global_shortcut = app.global_shortcut();
+        global_shortcut
+            .on_shortcut(shortcut, handle_shortcut)
+            .map_err(|e| e.to_string())?;
+        if let Some(previous) = previous {
+            let _ = global_shortcut.unregister(previous);
+        }
+    }
+
+    let mut settings = load_settings();
+    match action.as_str() {
+        "popup" => settings.popup_shortcut = combo,
+        "widget" => settings.widget_shortcut = combo,
+        _ => settings.quit_shortcut = Some(combo),
+    }
+    save_settings(&settings)?;
+    refresh_menu_labels(&app);
+    Ok(())
+}
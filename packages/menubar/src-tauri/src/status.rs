@@ -0,0 +1,173 @@
+//! Server status reported to the frontend.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::background::{sleep_unless_shutdown, spawn_background};
+use crate::now_millis;
+use crate::server::{
+    adopted_port, is_pid_running, is_server_reachable, load_pid_info, observe_server, server_port,
+};
+use crate::server_http::ping_health;
+use crate::settings::{cached_settings, TrayTooltipDetail};
+use crate::tray::{
+    set_tray_menu_open, set_tray_tooltip, TRAY_MENU_MAX_PAUSE_MS, TRAY_MENU_OPEN,
+    TRAY_MENU_OPENED_MS,
+};
+use crate::watchdog::is_watchdog_paused;
+
+#[derive(Serialize, Clone, PartialEq)]
+pub(crate) struct ServerStatus {
+    pub(crate) reachable: bool,
+    /// Reachable and answering the health endpoint with 2xx; the "green" state.
+    healthy: bool,
+    pub(crate) pid: Option<u64>,
+    pid_running: bool,
+    pub(crate) port: u16,
+    adopted: bool,
+    watchdog_paused: bool,
+}
+
+/// Result of the last health ping, refreshed by the watchdog and the status
+/// poller so building a status never waits on an HTTP round trip.
+static SERVER_HEALTHY: AtomicBool = AtomicBool::new(false);
+
+/// Ping the health endpoint and cache the result. Blocking; background
+/// threads only.
+pub(crate) fn refresh_server_health() {
+    SERVER_HEALTHY.store(ping_health().is_ok(), Ordering::SeqCst);
+}
+
+/// Status from the last probe, for callers that mustn't block on one.
+static LAST_SERVER_STATUS: Mutex<Option<ServerStatus>> = Mutex::new(None);
+
+pub(crate) fn cached_server_status() -> Option<ServerStatus> {
+    LAST_SERVER_STATUS.lock().ok().and_then(|status| status.clone())
+}
+
+/// Probe the server (blocking up to the connect timeout) and cache the result.
+fn current_server_status() -> ServerStatus {
+    let pid = load_pid_info().map(|info| info.pid);
+    let reachable = is_server_reachable();
+    observe_server(reachable);
+    let status = ServerStatus {
+        reachable,
+        healthy: reachable && SERVER_HEALTHY.load(Ordering::SeqCst),
+        pid,
+        pid_running: pid.is_some_and(is_pid_running),
+        port: server_port(),
+        adopted: adopted_port().is_some(),
+        watchdog_paused: is_watchdog_paused(),
+    };
+    if let Ok(mut last) = LAST_SERVER_STATUS.lock() {
+        *last = Some(status.clone());
+    }
+    status
+}
+
+/// Floor for `min_poll_interval_ms`, so a bad setting can't spin the poller.
+pub(crate) const MIN_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Poll bounds from settings, sanitized so min <= max.
+fn status_poll_bounds() -> (Duration, Duration) {
+    let settings = cached_settings();
+    let min = Duration::from_millis(settings.min_poll_interval_ms).max(MIN_STATUS_POLL_INTERVAL);
+    let max = Duration::from_millis(settings.max_poll_interval_ms).max(min);
+    (min, max)
+}
+
+/// Whether the frontend has opted in to `server-status` events.
+static STATUS_STREAM_ENABLED: AtomicBool = AtomicBool::new(false);
+/// Last status emitted, so unchanged polls don't re-emit.
+static LAST_EMITTED_STATUS: Mutex<Option<ServerStatus>> = Mutex::new(None);
+
+/// Adaptive poll: fast right after a transition, backing off while the
+/// status holds steady. Paused while the tray menu is open, since the
+/// connect timeout can make the menu stutter.
+pub(crate) fn run_status_poller(app: tauri::AppHandle) {
+    let mut interval = status_poll_bounds().0;
+    while sleep_unless_shutdown(interval) {
+        let (min, max) = status_poll_bounds();
+        if TRAY_MENU_OPEN.load(Ordering::SeqCst) {
+            // A dismissal we never heard about mustn't stall the poller
+            let opened = TRAY_MENU_OPENED_MS.load(Ordering::SeqCst);
+            if now_millis().saturating_sub(opened) < TRAY_MENU_MAX_PAUSE_MS {
+                interval = min;
+                continue;
+            }
+            set_tray_menu_open(&app, false);
+        }
+        interval = match poll_status_once(&app) {
+            Some(true) | None => min,
+            Some(false) => (interval * 2).clamp(min, max),
+        };
+    }
+}
+
+/// One poll: refresh the detailed tooltip and emit `server-status`, for
+/// whichever is enabled. `None` if neither is, else whether the status
+/// changed.
+pub(crate) fn poll_status_once(app: &tauri::AppHandle) -> Option<bool> {
+    let streaming = STATUS_STREAM_ENABLED.load(Ordering::SeqCst);
+    let detailed = cached_settings().tray_tooltip_detail == TrayTooltipDetail::Detailed;
+    if !streaming && !detailed {
+        return None;
+    }
+    refresh_server_health();
+    let status = current_server_status();
+    if detailed {
+        set_tray_tooltip(app, Some(&status));
+    }
+    Some(streaming && emit_status_if_changed(app, status))
+}
+
+/// Emit `server-status` if it differs from the last emitted one; returns
+/// whether it did.
+fn emit_status_if_changed(app: &tauri::AppHandle, status: ServerStatus) -> bool {
+    let Ok(mut last) = LAST_EMITTED_STATUS.lock() else {
+        return false;
+    };
+    if last.as_ref() == Some(&status) {
+        return false;
+    }
+    let _ = app.emit("server-status", &status);
+    *last = Some(status);
+    true
+}
+
+/// Re-ping health and emit the status if it changed. Blocking; background
+/// threads only.
+pub(crate) fn emit_fresh_status(app: &tauri::AppHandle) {
+    refresh_server_health();
+    emit_status_if_changed(app, current_server_status());
+}
+
+/// Probe the server now. Off the main thread: the reachability check can
+/// take up to its connect timeout.
+#[tauri::command]
+pub(crate) async fn get_server_status() -> Result<ServerStatus, String> {
+    tauri::async_runtime::spawn_blocking(current_server_status)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Start emitting `server-status` events on change. Emits the current status
+/// immediately so subscribers don't wait for the next poll.
+#[tauri::command]
+pub(crate) fn start_status_stream(app: tauri::AppHandle) {
+    STATUS_STREAM_ENABLED.store(true, Ordering::SeqCst);
+    if let Ok(mut last) = LAST_EMITTED_STATUS.lock() {
+        *last = None;
+    }
+    spawn_background("status-snapshot", move || {
+        emit_fresh_status(&app);
+    });
+}
+
+#[tauri::command]
+pub(crate) fn stop_status_stream() {
+    STATUS_STREAM_ENABLED.store(false, Ordering::SeqCst);
+}
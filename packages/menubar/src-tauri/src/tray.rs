@@ -0,0 +1,560 @@
+//! The tray icon and its menu.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconEvent};
+use tauri::{Emitter, Manager};
+
+use crate::background::spawn_background;
+use crate::dashboard::open_dashboard;
+use crate::main_window::toggle_main_window_from_tray;
+use crate::notifications::notify;
+use crate::server::{demand_server, SERVER_UP_SINCE_MS};
+use crate::settings::{cached_settings, load_settings, save_settings, TrayTooltipDetail};
+use crate::shortcuts::{format_shortcut, popup_shortcut, widget_shortcut};
+use crate::status::{cached_server_status, poll_status_once, ServerStatus};
+use crate::watchdog::is_watchdog_paused;
+use crate::widget::{reload_webview, toggle_widget, SHOW_WIDGET};
+use crate::{now_millis, request_quit};
+
+/// Text last written to the tray tooltip.
+static TRAY_TOOLTIP: Mutex<String> = Mutex::new(String::new());
+
+/// Compact duration for tooltips, e.g. "2h 13m", "5m", "40s".
+fn format_uptime(ms: u64) -> String {
+    let secs = ms / 1000;
+    match (secs / 3600, (secs % 3600) / 60) {
+        (0, 0) => format!("{secs}s"),
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
+fn tray_tooltip(status: Option<&ServerStatus>) -> String {
+    let mut text = String::from("Hexdeck");
+    if let Some(status) = status {
+        if status.reachable {
+            text.push_str(" — server up");
+            if let Some(pid) = status.pid {
+                text.push_str(&format!(" · pid {pid}"));
+            }
+            let since = SERVER_UP_SINCE_MS.load(Ordering::SeqCst);
+            if since > 0 {
+                text.push_str(&format!(" · up {}", format_uptime(now_millis().saturating_sub(since))));
+            }
+        } else {
+            text.push_str(" — server down");
+        }
+        text.push_str(&format!(" · port {}", status.port));
+    }
+    if is_watchdog_paused() {
+        text.push_str(" (watchdog paused)");
+    }
+    text
+}
+
+/// Refresh the tooltip. In `Detailed` mode it describes `status`, or the
+/// last probed status if none is given; it never probes itself.
+pub(crate) fn set_tray_tooltip(app: &tauri::AppHandle, status: Option<&ServerStatus>) {
+    let Some(tray) = app.tray_by_id("main-tray") else {
+        return;
+    };
+    let text = if cached_settings().tray_tooltip_detail == TrayTooltipDetail::Detailed {
+        match status {
+            Some(status) => tray_tooltip(Some(status)),
+            None => tray_tooltip(cached_server_status().as_ref()),
+        }
+    } else {
+        tray_tooltip(None)
+    };
+    let _ = tray.set_tooltip(Some(&text));
+    if let Ok(mut current) = TRAY_TOOLTIP.lock() {
+        *current = text;
+    }
+}
+
+#[tauri::command]
+pub(crate) fn set_tray_tooltip_detail(app: tauri::AppHandle, detail: TrayTooltipDetail) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.tray_tooltip_detail = detail;
+    save_settings(&settings)?;
+    set_tray_tooltip(&app, None);
+    Ok(())
+}
+
+/// Tray status colors. The first entry is the fallback for unknown colors.
+const TRAY_COLORS: &[(&str, [u8; 3])] = &[
+    ("grey", [0x6b, 0x6b, 0x7b]),
+    ("green", [0x00, 0xe8, 0x7b]),
+    ("yellow", [0xff, 0xc4, 0x4d]),
+    ("red", [0xff, 0x4d, 0x6a]),
+    ("blue", [0x4d, 0x9f, 0xff]),
+    // Server restarting/updating
+    ("orange", [0xff, 0x8c, 0x32]),
+];
+
+/// Tray mark at 1x; `currentColor` is replaced with the requested fill.
+const TRAY_ICON_SVG: &str = include_str!("../icons/tray.svg");
+
+/// Parse `#rrggbb` (the `#` is optional) into RGB.
+pub(crate) fn parse_hex_color(hex: &str) -> Result<[u8; 3], String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid color: {hex} (expected #rrggbb)"));
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|e| e.to_string());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Fill for a tray color: a `#rrggbb` value as-is, idle grey replaced by the
+/// accent when one is set, otherwise the status table.
+fn tray_fill(color: &str) -> Result<[u8; 3], String> {
+    if color.starts_with('#') {
+        return parse_hex_color(color);
+    }
+    if color == "grey" {
+        if let Some(rgb) = cached_settings().accent_color.and_then(|hex| parse_hex_color(&hex).ok()) {
+            return Ok(rgb);
+        }
+    }
+    Ok(TRAY_COLORS
+        .iter()
+        .find(|(name, _)| *name == color)
+        .unwrap_or(&TRAY_COLORS[0])
+        .1)
+}
+
+/// (fill, scale in hundredths) of a rasterized tray icon.
+type TrayIconKey = ([u8; 3], u32);
+/// Rasterized PNGs by key.
+type TrayIconCache = HashMap<TrayIconKey, Vec<u8>>;
+
+static TRAY_ICON_CACHE: Mutex<Option<TrayIconCache>> = Mutex::new(None);
+/// Enough for the status colors, the accent and template black at a couple
+/// of scales; arbitrary `#rrggbb` colors would otherwise grow it forever.
+const MAX_TRAY_ICON_CACHE: usize = 16;
+
+fn render_tray_icon(rgb: [u8; 3], scale: f64) -> Result<Vec<u8>, String> {
+    let [r, g, b] = rgb;
+    let svg = TRAY_ICON_SVG.replace("currentColor", &format!("#{r:02x}{g:02x}{b:02x}"));
+    let tree = resvg::usvg::Tree::from_str(&svg, &resvg::usvg::Options::default())
+        .map_err(|e| e.to_string())?;
+    let scale = scale as f32;
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(scale)
+        .ok_or("Invalid tray icon scale")?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or("Failed to allocate tray icon")?;
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+/// Tray icon for `color`, rasterized for the primary display's scale (where
+/// the menu bar is).
+fn tray_icon_image(app: &tauri::AppHandle, color: &str) -> Result<Image<'static>, String> {
+    let rgb = tray_fill(color)?;
+    let scale = app
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .map_or(2.0, |m| m.scale_factor());
+    let key = (rgb, (scale * 100.0).round() as u32);
+
+    let mut cache = TRAY_ICON_CACHE.lock().map_err(|e| e.to_string())?;
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if !cache.contains_key(&key) && cache.len() >= MAX_TRAY_ICON_CACHE {
+        cache.clear();
+    }
+    let png = match cache.entry(key) {
+        std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+        std::collections::hash_map::Entry::Vacant(entry) => entry.insert(render_tray_icon(rgb, scale)?),
+    };
+    Image::from_bytes(png)
+        .map(|image| image.to_owned())
+        .map_err(|e| e.to_string())
+}
+
+/// Color last applied to the tray icon.
+static TRAY_COLOR: Mutex<String> = Mutex::new(String::new());
+/// Color requested while the tray menu was open, applied once it closes.
+static PENDING_TRAY_COLOR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Whether `color` is drawn as a template image: only idle grey, and only
+/// with `tray_template` on, so status colors stay visible.
+fn is_template_color(color: &str) -> bool {
+    color == "grey" && cached_settings().tray_template
+}
+
+pub(crate) fn set_tray_color(app: &tauri::AppHandle, color: &str) -> Result<(), String> {
+    // Swapping the icon under an open menu can make it stutter; hold the
+    // color until the pause ends (see `set_tray_menu_open`)
+    if TRAY_MENU_OPEN.load(Ordering::SeqCst) {
+        if let Ok(mut pending) = PENDING_TRAY_COLOR.lock() {
+            *pending = Some(color.to_string());
+        }
+        return Ok(());
+    }
+    if let Ok(mut current) = TRAY_COLOR.lock() {
+        *current = color.to_string();
+    }
+    let template = is_template_color(color);
+    // Template images only use alpha; black keeps the mask unambiguous
+    let image = tray_icon_image(app, if template { "#000000" } else { color })?;
+
+    if let Some(tray) = app.tray_by_id("main-tray") {
+        tray.set_icon(Some(image)).map_err(|e| e.to_string())?;
+        tray.set_icon_as_template(template).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// The color most recently asked for, even if still held back by the menu.
+pub(crate) fn current_tray_color() -> String {
+    if let Some(pending) = PENDING_TRAY_COLOR.lock().ok().and_then(|p| p.clone()) {
+        return pending;
+    }
+    TRAY_COLOR
+        .lock()
+        .ok()
+        .filter(|color| !color.is_empty())
+        .map(|color| color.clone())
+        .unwrap_or_else(|| "grey".to_string())
+}
+
+/// Fire the events (and tray color) a real server failure/update would,
+/// without touching the server, so failure UX can be exercised on demand.
+/// Only available with `debug_mode` on.
+#[tauri::command]
+pub(crate) fn simulate_server_event(app: tauri::AppHandle, kind: String) -> Result<(), String> {
+    if !cached_settings().debug_mode {
+        return Err("simulate_server_event requires debug_mode".into());
+    }
+    let (event, color, title) = match kind.as_str() {
+        "failed" => ("server-failed", "red", "Hexdeck server failed to start"),
+        "version-mismatch" => (
+            "server-version-mismatch",
+            "yellow",
+            "Hexdeck server version doesn't match the app",
+        ),
+        "update-available" => ("update-available", "blue", "A Hexdeck update is available"),
+        "ready" => ("server-ready", "green", "Hexdeck server is ready"),
+        other => {
+            return Err(format!(
+                "Unknown event kind: {other} (expected failed, version-mismatch, update-available, ready)"
+            ))
+        }
+    };
+    let payload = serde_json::json!({ "simulated": true, "kind": kind });
+    app.emit(event, payload).map_err(|e| e.to_string())?;
+    set_tray_color(&app, color)?;
+    let _ = notify(&app, title, "Simulated event (debug mode)");
+    Ok(())
+}
+
+/// Whether the tray's context menu is open. Best effort: set when the
+/// right-click that opens it arrives, cleared by the next menu or tray event,
+/// since dismissing the menu without choosing an item reports nothing.
+pub(crate) static TRAY_MENU_OPEN: AtomicBool = AtomicBool::new(false);
+/// Epoch-millis the tray menu last opened.
+pub(crate) static TRAY_MENU_OPENED_MS: AtomicU64 = AtomicU64::new(0);
+/// Longest the status poller and tray color stay paused for an open menu,
+/// given that dismissals can go unreported.
+pub(crate) const TRAY_MENU_MAX_PAUSE_MS: u64 = 5_000;
+
+/// Track the tray menu opening and closing. On close, catch up on what was
+/// held back while it was open: the requested tray color and a status poll.
+pub(crate) fn set_tray_menu_open(app: &tauri::AppHandle, open: bool) {
+    if open {
+        TRAY_MENU_OPENED_MS.store(now_millis(), Ordering::SeqCst);
+    }
+    if TRAY_MENU_OPEN.swap(open, Ordering::SeqCst) && !open {
+        let pending = PENDING_TRAY_COLOR.lock().ok().and_then(|mut p| p.take());
+        if let Some(color) = pending {
+            let _ = set_tray_color(app, &color);
+        }
+        let app = app.clone();
+        spawn_background("tray-menu-closed", move || {
+            poll_status_once(&app);
+        });
+    }
+}
+
+/// Epoch-millis of the last left click on the tray, for debouncing.
+static LAST_TRAY_TOGGLE_MS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize)]
+pub(crate) struct TrayStateInfo {
+    color: String,
+    tooltip: String,
+    menu_open: bool,
+    rect: Option<TrayRect>,
+}
+
+/// Snapshot of the tray icon for support and UI mirroring; `None` in
+/// widget-only mode.
+#[tauri::command]
+pub(crate) fn get_tray_state(app: tauri::AppHandle) -> Option<TrayStateInfo> {
+    let tray = app.tray_by_id("main-tray")?;
+    let scale = app
+        .get_webview_window("main")
+        .and_then(|w| w.scale_factor().ok())
+        .unwrap_or(1.0);
+    Some(TrayStateInfo {
+        color: current_tray_color(),
+        tooltip: TRAY_TOOLTIP
+            .lock()
+            .ok()
+            .filter(|text| !text.is_empty())
+            .map_or_else(|| "Hexdeck".to_string(), |text| text.clone()),
+        menu_open: TRAY_MENU_OPEN.load(Ordering::SeqCst),
+        rect: tray_rect_physical(&tray, scale),
+    })
+}
+
+/// Order `cycle_tray_color` steps through the status colors.
+const TRAY_COLOR_CYCLE: [&str; 5] = ["grey", "green", "blue", "yellow", "red"];
+
+/// Advance the tray to the next status color, for visual QA. Only available
+/// with `debug_mode` on. Returns the color now shown.
+#[tauri::command]
+pub(crate) fn cycle_tray_color(app: tauri::AppHandle) -> Result<String, String> {
+    if !cached_settings().debug_mode {
+        return Err("cycle_tray_color requires debug_mode".into());
+    }
+    let current = current_tray_color();
+    let next = TRAY_COLOR_CYCLE
+        .iter()
+        .position(|color| *color == current)
+        .map_or(0, |i| (i + 1) % TRAY_COLOR_CYCLE.len());
+    let color = TRAY_COLOR_CYCLE[next];
+    set_tray_color(&app, color)?;
+    Ok(color.to_string())
+}
+
+/// Set (or with `None`, clear) the branding accent. Re-tints the tray if it's
+/// idle and emits `accent-color` so the widget can restyle.
+#[tauri::command]
+pub(crate) fn set_accent_color(app: tauri::AppHandle, hex: Option<String>) -> Result<(), String> {
+    let hex = match hex.filter(|h| !h.is_empty()) {
+        Some(h) => {
+            let [r, g, b] = parse_hex_color(&h)?;
+            Some(format!("#{r:02x}{g:02x}{b:02x}"))
+        }
+        None => None,
+    };
+    let mut settings = load_settings();
+    settings.accent_color = hex.clone();
+    save_settings(&settings)?;
+    if current_tray_color() == "grey" {
+        set_tray_color(&app, "grey")?;
+    }
+    app.emit("accent-color", &hex).map_err(|e| e.to_string())
+}
+
+/// Switch the idle tray icon between template (menu-bar tinted) and colored,
+/// without a relaunch.
+#[tauri::command]
+pub(crate) fn set_tray_template(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.tray_template = enabled;
+    save_settings(&settings)?;
+    set_tray_color(&app, &current_tray_color())
+}
+
+#[tauri::command]
+pub(crate) fn update_tray_icon(app: tauri::AppHandle, color: String) -> Result<(), String> {
+    set_tray_color(&app, &color)
+}
+
+/// Menu items whose labels/state change after the menu is built.
+pub(crate) struct TrayMenuItems {
+    pub(crate) show_widget: CheckMenuItem<tauri::Wry>,
+    shortcut_hint: MenuItem<tauri::Wry>,
+}
+
+/// The tray icon and the items of its current menu, replaced on rebuild.
+struct TrayState {
+    tray: TrayIcon<tauri::Wry>,
+    items: Mutex<TrayMenuItems>,
+}
+
+pub(crate) fn with_tray_items(app: &tauri::AppHandle, f: impl FnOnce(&TrayMenuItems)) {
+    if let Some(state) = app.try_state::<TrayState>() {
+        if let Ok(items) = state.items.lock() {
+            f(&items);
+        }
+    }
+}
+
+fn widget_menu_label() -> String {
+    format!("Show Floating Widget  ({})", format_shortcut(&widget_shortcut()))
+}
+
+fn popup_menu_label() -> String {
+    format!("Toggle Popup  ({})", format_shortcut(&popup_shortcut()))
+}
+
+pub(crate) fn refresh_menu_labels(app: &tauri::AppHandle) {
+    with_tray_items(app, |items| {
+        let _ = items.show_widget.set_text(widget_menu_label());
+        let _ = items.shortcut_hint.set_text(popup_menu_label());
+    });
+}
+
+/// Build the right-click context menu from current state. Item ids are what
+/// the tray's `on_menu_event` handler dispatches on, so they must stay stable.
+fn build_tray_menu(app: &tauri::AppHandle) -> tauri::Result<(Menu<tauri::Wry>, TrayMenuItems)> {
+    let show_widget_item = CheckMenuItem::with_id(
+        app,
+        "toggle_widget",
+        widget_menu_label(),
+        true,
+        SHOW_WIDGET.load(Ordering::SeqCst),
+        None::<&str>,
+    )?;
+    let shortcut_hint = MenuItem::with_id(
+        app,
+        "shortcut_hint",
+        popup_menu_label(),
+        false,
+        None::<&str>,
+    )?;
+    let open_dashboard = MenuItem::with_id(app, "open_dashboard", "Open Dashboard", true, None::<&str>)?;
+    let reload_popup = MenuItem::with_id(app, "reload_main", "Reload Popup", true, None::<&str>)?;
+    let reload_widget = MenuItem::with_id(app, "reload_widget", "Reload Widget", true, None::<&str>)?;
+    let advanced = Submenu::with_id_and_items(
+        app,
+        "advanced",
+        "Advanced",
+        true,
+        &[&reload_popup, &reload_widget],
+    )?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[&show_widget_item, &shortcut_hint, &open_dashboard, &advanced, &quit],
+    )?;
+    let items = TrayMenuItems {
+        show_widget: show_widget_item,
+        shortcut_hint,
+    };
+    Ok((menu, items))
+}
+
+/// Replace the tray menu with one built from current state. A no-op in
+/// widget-only mode.
+pub(crate) fn rebuild_tray_menu(app: &tauri::AppHandle) -> Result<(), String> {
+    let Some(state) = app.try_state::<TrayState>() else {
+        return Ok(());
+    };
+    let (menu, items) = build_tray_menu(app).map_err(|e| e.to_string())?;
+    state.tray.set_menu(Some(menu)).map_err(|e| e.to_string())?;
+    *state.items.lock().map_err(|e| e.to_string())? = items;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn refresh_tray_menu(app: tauri::AppHandle) -> Result<(), String> {
+    rebuild_tray_menu(&app)
+}
+
+/// Create the menu bar icon and its right-click menu.
+pub(crate) fn build_tray(app: &tauri::AppHandle, tray_click_guard: &'static AtomicBool) -> tauri::Result<()> {
+    let template = is_template_color("grey");
+    let grey_icon = tray_icon_image(app, if template { "#000000" } else { "grey" })
+        .expect("Failed to load tray icon");
+    let (menu, items) = build_tray_menu(app)?;
+
+    let guard_for_tray = tray_click_guard;
+    let tray = tauri::tray::TrayIconBuilder::with_id("main-tray")
+        .icon(grey_icon)
+        .icon_as_template(template)
+        .tooltip("Hexdeck")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(move |tray, event| {
+            match event {
+                TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } => {
+                    set_tray_menu_open(tray.app_handle(), false);
+                    // A double-click would otherwise show then immediately hide
+                    let now = now_millis();
+                    let last = LAST_TRAY_TOGGLE_MS.swap(now, Ordering::SeqCst);
+                    if now.saturating_sub(last) < cached_settings().tray_click_debounce_ms {
+                        return;
+                    }
+                    let app = tray.app_handle();
+                    toggle_main_window_from_tray(app, tray, guard_for_tray);
+                }
+                TrayIconEvent::Click { button: MouseButton::Right, .. } => {
+                    set_tray_menu_open(tray.app_handle(), true);
+                }
+                _ => set_tray_menu_open(tray.app_handle(), false),
+            }
+        })
+        .on_menu_event(move |app, event| {
+            set_tray_menu_open(app, false);
+            match event.id.as_ref() {
+                "toggle_widget" => {
+                    toggle_widget(app);
+                }
+                "open_dashboard" => {
+                    demand_server(app);
+                    // osascript can take a moment; keep it off the main thread
+                    spawn_background("open-dashboard", open_dashboard);
+                }
+                "reload_main" => {
+                    let _ = reload_webview(app, "main");
+                }
+                "reload_widget" => {
+                    let _ = reload_webview(app, "widget");
+                }
+                "quit" => {
+                    request_quit(app);
+                }
+                _ => {}
+            }
+        })
+        .build(app)?;
+
+    app.manage(TrayState {
+        tray,
+        items: Mutex::new(items),
+    });
+    Ok(())
+}
+
+/// Tray icon bounds in physical pixels.
+#[derive(Serialize, Clone, Copy)]
+pub(crate) struct TrayRect {
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+}
+
+/// The tray icon's bounds, converting logical units with `scale`.
+pub(crate) fn tray_rect_physical(tray: &tauri::tray::TrayIcon, scale: f64) -> Option<TrayRect> {
+    let rect = tray.rect().ok().flatten()?;
+
+    // Extract physical coordinates from the Position/Size enums
+    let (x, y) = match rect.position {
+        tauri::Position::Physical(p) => (p.x as f64, p.y as f64),
+        tauri::Position::Logical(p) => (p.x * scale, p.y * scale),
+    };
+    let (width, height) = match rect.size {
+        tauri::Size::Physical(s) => (s.width as f64, s.height as f64),
+        tauri::Size::Logical(s) => (s.width * scale, s.height * scale),
+    };
+    Some(TrayRect { x, y, width, height })
+}
@@ -0,0 +1,138 @@
+//! Downloading and installing updates.
+
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::background::{shutdown_background_threads, sleep_unless_shutdown};
+use crate::paths::bootstrap_dir;
+use crate::server::{is_pid_running, kill_server, load_pid_info, pid_file};
+use crate::settings::cached_settings;
+use crate::widget_position::flush_pending_writes;
+
+#[derive(Serialize, Clone)]
+struct UpdateProgress {
+    downloaded: u64,
+    total: Option<u64>,
+    percent: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub(crate) enum UpdateError {
+    AlreadyInProgress,
+    NoUpdate,
+    Check(String),
+    Download(String),
+}
+
+static UPDATE_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+async fn download_and_install_update(app: &tauri::AppHandle) -> Result<String, UpdateError> {
+    use tauri_plugin_updater::UpdaterExt;
+
+    let updater = app.updater().map_err(|e| UpdateError::Check(e.to_string()))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| UpdateError::Check(e.to_string()))?
+        .ok_or(UpdateError::NoUpdate)?;
+
+    let progress_app = app.clone();
+    let mut downloaded: u64 = 0;
+    update
+        .download_and_install(
+            move |chunk_length, total| {
+                downloaded += chunk_length as u64;
+                let percent = total
+                    .filter(|&total| total > 0)
+                    .map(|total| downloaded as f64 / total as f64 * 100.0);
+                let _ = progress_app.emit(
+                    "update-progress",
+                    UpdateProgress { downloaded, total, percent },
+                );
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| UpdateError::Download(e.to_string()))?;
+
+    let _ = app.emit("update-ready", &update.version);
+    Ok(update.version.clone())
+}
+
+/// Download and stage the pending update, emitting `update-progress` while
+/// downloading and `update-ready` once installed. Safe to call again after
+/// a failure; concurrent calls are rejected.
+#[tauri::command]
+pub(crate) async fn install_update(app: tauri::AppHandle) -> Result<String, UpdateError> {
+    if UPDATE_IN_PROGRESS.swap(true, Ordering::SeqCst) {
+        return Err(UpdateError::AlreadyInProgress);
+    }
+    let result = download_and_install_update(&app).await;
+    UPDATE_IN_PROGRESS.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Marker left by `relaunch_app` so the next launch knows it follows an
+/// update. `app.restart()` can't pass arguments, hence a file.
+fn relaunch_marker_file() -> Option<PathBuf> {
+    bootstrap_dir().map(|d| d.join("relaunch"))
+}
+
+/// Markers older than this are leftovers from a relaunch that never started.
+const RELAUNCH_MARKER_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Consume the relaunch marker, returning whether this launch is a fresh
+/// update relaunch.
+pub(crate) fn take_relaunch_marker() -> bool {
+    let Some(path) = relaunch_marker_file() else {
+        return false;
+    };
+    let fresh = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.elapsed().ok())
+        .is_some_and(|age| age < RELAUNCH_MARKER_MAX_AGE);
+    let _ = fs::remove_file(&path);
+    fresh
+}
+
+/// After an update relaunch the previous server may still be shutting down.
+/// Wait for it to go, then clear its PID file so we don't adopt a dying one.
+pub(crate) fn settle_after_relaunch() {
+    if !sleep_unless_shutdown(Duration::from_millis(cached_settings().relaunch_settle_ms)) {
+        return;
+    }
+    if let Some(info) = load_pid_info() {
+        for _ in 0..10 {
+            if !is_pid_running(info.pid) {
+                break;
+            }
+            if !sleep_unless_shutdown(Duration::from_millis(300)) {
+                return;
+            }
+        }
+        if !is_pid_running(info.pid) {
+            if let Some(path) = pid_file() {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Relaunch into the installed update. The managed server is stopped first so
+/// the new version starts its own.
+#[tauri::command]
+pub(crate) fn relaunch_app(app: tauri::AppHandle) {
+    let _ = flush_pending_writes();
+    shutdown_background_threads();
+    kill_server();
+    if let Some(path) = relaunch_marker_file() {
+        let _ = fs::write(path, "");
+    }
+    app.restart();
+}
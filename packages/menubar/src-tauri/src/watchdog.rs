@@ -0,0 +1,61 @@
+//! Watchdog that respawns the server when it goes away.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::background::{sleep_unless_shutdown, spawn_background};
+use crate::logs::rotate_logs;
+use crate::now_secs;
+use crate::server::{ensure_server_running, is_server_wanted};
+use crate::status::{emit_fresh_status, refresh_server_health};
+use crate::tray::set_tray_tooltip;
+
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Epoch-seconds until which the watchdog is paused; 0 when running,
+/// `u64::MAX` when paused until `resume_watchdog`.
+static WATCHDOG_PAUSED_UNTIL: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn is_watchdog_paused() -> bool {
+    now_secs() < WATCHDOG_PAUSED_UNTIL.load(Ordering::SeqCst)
+}
+
+/// Restart the server whenever it goes away, unless paused for maintenance.
+pub(crate) fn run_watchdog(app: tauri::AppHandle) {
+    rotate_logs();
+    let mut was_paused = false;
+    while sleep_unless_shutdown(WATCHDOG_INTERVAL) {
+        let paused = is_watchdog_paused();
+        if paused != was_paused {
+            // Covers the timed auto-resume, which no command announces
+            set_tray_tooltip(&app, None);
+            emit_fresh_status(&app);
+            was_paused = paused;
+        }
+        if !paused && is_server_wanted() {
+            ensure_server_running(&app);
+        }
+        refresh_server_health();
+        rotate_logs();
+    }
+}
+
+/// Stop the watchdog from restarting the server, for `seconds` or until
+/// `resume_watchdog` when `None`.
+#[tauri::command]
+pub(crate) fn pause_watchdog(app: tauri::AppHandle, seconds: Option<u64>) {
+    let until = match seconds {
+        Some(secs) => now_secs().saturating_add(secs),
+        None => u64::MAX,
+    };
+    WATCHDOG_PAUSED_UNTIL.store(until, Ordering::SeqCst);
+    set_tray_tooltip(&app, None);
+    spawn_background("status-snapshot", move || emit_fresh_status(&app));
+}
+
+#[tauri::command]
+pub(crate) fn resume_watchdog(app: tauri::AppHandle) {
+    WATCHDOG_PAUSED_UNTIL.store(0, Ordering::SeqCst);
+    set_tray_tooltip(&app, None);
+    spawn_background("status-snapshot", move || emit_fresh_status(&app));
+}
@@ -0,0 +1,632 @@
+//! The widget window.
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::background::{sleep_unless_shutdown, spawn_background};
+use crate::logs::open_server_log;
+use crate::now_millis;
+use crate::paths::settings_file;
+use crate::server::force_reconnect;
+use crate::settings::{
+    cached_settings, load_settings, save_settings, WidgetDownAction, WidgetSettings, WidgetStacking,
+};
+use crate::tray::with_tray_items;
+use crate::widget_position::{is_widget_dragging, pin_widget_to_monitor};
+
+pub(crate) fn load_widget_visibility() -> bool {
+    let Some(path) = settings_file() else {
+        return true;
+    };
+    let Ok(data) = fs::read_to_string(path) else {
+        return true;
+    };
+    let Ok(settings) = serde_json::from_str::<WidgetSettings>(&data) else {
+        return true;
+    };
+    settings.show_widget
+}
+
+fn save_widget_visibility(show_widget: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.show_widget = show_widget;
+    save_settings(&settings)
+}
+
+pub(crate) fn apply_widget_visibility(app: &tauri::AppHandle, show_widget: bool) {
+    if let Some(widget) = app.get_webview_window("widget") {
+        if show_widget {
+            let settings = cached_settings();
+            apply_widget_spaces(&widget, &settings);
+            set_window_alpha(&widget, settings.widget_opacity);
+            let _ = widget.set_shadow(settings.widget_shadow);
+            let _ = apply_widget_vibrancy(&widget, settings.widget_vibrancy.as_deref());
+            note_widget_activity();
+            pin_widget_to_monitor(&widget);
+            WIDGET_SHOW_GUARD.store(true, Ordering::SeqCst);
+            let _ = widget.show();
+            if settings.widget_focus_flash_on_show {
+                flash_widget_focus(&widget);
+            }
+            apply_window_stacking(app);
+        } else {
+            let _ = widget.hide();
+        }
+    }
+}
+
+/// How long the widget holds focus during the show-time flash.
+#[cfg(target_os = "macos")]
+const WIDGET_FOCUS_FLASH: Duration = Duration::from_millis(60);
+
+/// Focus the widget just long enough to activate mouse tracking, then hand
+/// focus back to whichever app had it, so showing the widget (e.g. at
+/// launch) doesn't steal the user's focus. Callers may be on any thread, so
+/// the AppKit work is dispatched to the main thread.
+#[cfg(target_os = "macos")]
+fn flash_widget_focus(widget: &tauri::WebviewWindow) {
+    use objc2::{class, msg_send, runtime::AnyObject};
+    let handle = widget.clone();
+    let _ = widget.run_on_main_thread(move || {
+        let previous_pid: Option<i32> = unsafe {
+            let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let frontmost: *mut AnyObject = msg_send![&*workspace, frontmostApplication];
+            (!frontmost.is_null()).then(|| msg_send![&*frontmost, processIdentifier])
+        };
+        let _ = handle.set_focus();
+        let Some(pid) = previous_pid.filter(|pid| *pid != std::process::id() as i32) else {
+            return;
+        };
+        spawn_background("widget-focus-flash", move || {
+            if !sleep_unless_shutdown(WIDGET_FOCUS_FLASH) {
+                return;
+            }
+            // Handing focus back isn't the user clicking away
+            WIDGET_SHOW_GUARD.store(true, Ordering::SeqCst);
+            let _ = handle.run_on_main_thread(move || unsafe {
+                let previous: *mut AnyObject =
+                    msg_send![class!(NSRunningApplication), runningApplicationWithProcessIdentifier: pid];
+                if !previous.is_null() {
+                    let _: bool = msg_send![&*previous, activateWithOptions: 0usize];
+                }
+            });
+        });
+    });
+}
+
+/// Mouse tracking doesn't depend on focus elsewhere, and there's no portable
+/// way to hand focus back, so the flash is a plain focus.
+#[cfg(not(target_os = "macos"))]
+fn flash_widget_focus(widget: &tauri::WebviewWindow) {
+    let _ = widget.set_focus();
+}
+
+#[tauri::command]
+pub(crate) fn set_widget_focus_flash_on_show(enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.widget_focus_flash_on_show = enabled;
+    save_settings(&settings)
+}
+
+/// Set when the widget is summoned, so the focus shuffle of showing it
+/// can't immediately auto-hide it; cleared once it gains focus.
+static WIDGET_SHOW_GUARD: AtomicBool = AtomicBool::new(false);
+/// Give focus time to land: moving it to our own popup isn't "clicking away".
+const WIDGET_HIDE_DELAY: Duration = Duration::from_millis(150);
+
+/// `hide_widget_on_focus_loss` policy, mirroring `handle_main_focus_change`.
+pub(crate) fn handle_widget_focus_change(app: &tauri::AppHandle, focused: bool) {
+    if focused {
+        WIDGET_SHOW_GUARD.store(false, Ordering::SeqCst);
+        return;
+    }
+    if WIDGET_SHOW_GUARD.swap(false, Ordering::SeqCst) || !cached_settings().hide_widget_on_focus_loss {
+        return;
+    }
+    let app = app.clone();
+    spawn_background("widget-hide-delay", move || {
+        if !sleep_unless_shutdown(WIDGET_HIDE_DELAY) || is_widget_dragging() {
+            return;
+        }
+        let ours_focused = ["widget", "main"].iter().any(|label| {
+            app.get_webview_window(label)
+                .is_some_and(|w| w.is_focused().unwrap_or(false))
+        });
+        if !ours_focused {
+            if let Some(widget) = app.get_webview_window("widget") {
+                let _ = widget.hide();
+            }
+        }
+    });
+}
+
+/// Both windows are always-on-top, which leaves their order to whichever was
+/// raised last. Put the popup and widget on distinct floating levels so
+/// `widget_stacking` decides.
+#[cfg(target_os = "macos")]
+pub(crate) fn apply_window_stacking(app: &tauri::AppHandle) {
+    use objc2::{msg_send, runtime::AnyObject};
+    /// NSFloatingWindowLevel; the upper window sits one above it.
+    const FLOATING_LEVEL: isize = 3;
+    let widget_above = cached_settings().widget_stacking == WidgetStacking::AbovePopup;
+    for (label, upper) in [("main", !widget_above), ("widget", widget_above)] {
+        let Some(window) = app.get_webview_window(label) else {
+            continue;
+        };
+        let Ok(ns_window) = window.ns_window() else {
+            continue;
+        };
+        let ns_window = ns_window as usize;
+        let level = FLOATING_LEVEL + isize::from(upper);
+        let _ = window.run_on_main_thread(move || unsafe {
+            let ns_window = &*(ns_window as *mut AnyObject);
+            let _: () = msg_send![ns_window, setLevel: level];
+        });
+    }
+}
+
+/// Elsewhere there's a single topmost band; re-asserting always-on-top
+/// raises a window within it, so do the lower window first.
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn apply_window_stacking(app: &tauri::AppHandle) {
+    let order = match cached_settings().widget_stacking {
+        WidgetStacking::AbovePopup => ["main", "widget"],
+        WidgetStacking::BelowPopup => ["widget", "main"],
+    };
+    for label in order {
+        if let Some(window) = app.get_webview_window(label) {
+            if window.is_visible().unwrap_or(false) {
+                let _ = window.set_always_on_top(true);
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn set_widget_stacking(app: tauri::AppHandle, stacking: WidgetStacking) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.widget_stacking = stacking;
+    save_settings(&settings)?;
+    apply_window_stacking(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_widget_visible_on_all_spaces(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.widget_visible_on_all_spaces = enabled;
+    save_settings(&settings)?;
+    if let Some(widget) = app.get_webview_window("widget") {
+        apply_widget_spaces(&widget, &settings);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn get_widget_over_fullscreen() -> bool {
+    cached_settings().widget_over_fullscreen
+}
+
+#[tauri::command]
+pub(crate) fn set_widget_over_fullscreen(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.widget_over_fullscreen = enabled;
+    save_settings(&settings)?;
+    if let Some(widget) = app.get_webview_window("widget") {
+        apply_widget_spaces(&widget, &settings);
+    }
+    Ok(())
+}
+
+/// Joining all Spaces keeps the widget across desktop switches, but a
+/// full-screen app lives in its own Space, which the widget only enters as a
+/// full-screen auxiliary window. Entering it implies joining all Spaces.
+pub(crate) fn apply_widget_spaces(widget: &tauri::WebviewWindow, settings: &WidgetSettings) {
+    let all_spaces = settings.widget_visible_on_all_spaces || settings.widget_over_fullscreen;
+    let _ = widget.set_visible_on_all_workspaces(all_spaces);
+    set_fullscreen_auxiliary(widget, settings.widget_over_fullscreen);
+}
+
+#[cfg(target_os = "macos")]
+fn set_fullscreen_auxiliary(window: &tauri::WebviewWindow, enabled: bool) {
+    use objc2::{msg_send, runtime::AnyObject};
+    /// NSWindowCollectionBehaviorFullScreenAuxiliary
+    const FULL_SCREEN_AUXILIARY: usize = 1 << 8;
+    let Ok(ns_window) = window.ns_window() else {
+        return;
+    };
+    let ns_window = ns_window as usize;
+    // Queued after set_visible_on_all_workspaces, so this edits its result
+    let _ = window.run_on_main_thread(move || unsafe {
+        let ns_window = &*(ns_window as *mut AnyObject);
+        let behavior: usize = msg_send![ns_window, collectionBehavior];
+        let behavior = if enabled {
+            behavior | FULL_SCREEN_AUXILIARY
+        } else {
+            behavior & !FULL_SCREEN_AUXILIARY
+        };
+        let _: () = msg_send![ns_window, setCollectionBehavior: behavior];
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_fullscreen_auxiliary(_window: &tauri::WebviewWindow, _enabled: bool) {}
+
+/// Reload a window's page, for when its UI is stuck but the server is fine.
+pub(crate) fn reload_webview(app: &tauri::AppHandle, label: &str) -> Result<(), String> {
+    let window = app
+        .get_webview_window(label)
+        .ok_or_else(|| format!("Unknown window: {label}"))?;
+    window.eval("location.reload()").map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub(crate) fn reload_window(app: tauri::AppHandle, label: String) -> Result<(), String> {
+    reload_webview(&app, &label)
+}
+
+#[tauri::command]
+pub(crate) fn get_window_visibility(app: tauri::AppHandle, label: String) -> Result<bool, String> {
+    let window = app
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("Unknown window: {label}"))?;
+    window.is_visible().map_err(|e| e.to_string())
+}
+
+/// Called by the widget when it's clicked during an outage; runs the
+/// configured `widget_down_action` and returns which one it was.
+#[tauri::command]
+pub(crate) fn widget_clicked_while_down(app: tauri::AppHandle) -> Result<WidgetDownAction, String> {
+    let action = cached_settings().widget_down_action;
+    match action {
+        WidgetDownAction::Reconnect => force_reconnect(app),
+        WidgetDownAction::OpenLogs => open_server_log()?,
+        WidgetDownAction::Nothing => {}
+    }
+    Ok(action)
+}
+
+/// Whether the floating widget is currently enabled (mirrors the persisted setting).
+pub(crate) static SHOW_WIDGET: AtomicBool = AtomicBool::new(true);
+
+pub(crate) fn toggle_widget(app: &tauri::AppHandle) {
+    // Auto-hidden on focus loss still counts as off, so one press brings it back
+    let visible = SHOW_WIDGET.load(Ordering::SeqCst)
+        && app
+            .get_webview_window("widget")
+            .is_some_and(|w| w.is_visible().unwrap_or(false));
+    set_widget_shown(app, !visible);
+}
+
+/// Show or hide the widget, keeping the menu check and settings in sync.
+pub(crate) fn set_widget_shown(app: &tauri::AppHandle, show: bool) {
+    SHOW_WIDGET.store(show, Ordering::SeqCst);
+    with_tray_items(app, |items| {
+        let _ = items.show_widget.set_checked(show);
+    });
+    let _ = save_widget_visibility(show);
+    apply_widget_visibility(app, show);
+}
+
+// ─── Widget Vibrancy ───────────────────────────────────────────────────────
+
+pub(crate) const WIDGET_VIBRANCY_MATERIALS: [&str; 7] = [
+    "hud",
+    "sidebar",
+    "popover",
+    "menu",
+    "tooltip",
+    "window-background",
+    "under-window",
+];
+
+/// Apply an NSVisualEffect material behind the widget, or clear it for `None`.
+/// The window is already transparent, so the material shows through and
+/// composes with the opacity setting.
+#[cfg(target_os = "macos")]
+fn apply_widget_vibrancy(widget: &tauri::WebviewWindow, material: Option<&str>) -> Result<(), String> {
+    use tauri::utils::config::WindowEffectsConfig;
+    use tauri::window::{Effect, EffectState, EffectsBuilder};
+    let Some(material) = material else {
+        return widget
+            .set_effects(None::<WindowEffectsConfig>)
+            .map_err(|e| e.to_string());
+    };
+    let effect = match material {
+        "hud" => Effect::HudWindow,
+        "sidebar" => Effect::Sidebar,
+        "popover" => Effect::Popover,
+        "menu" => Effect::Menu,
+        "tooltip" => Effect::Tooltip,
+        "window-background" => Effect::WindowBackground,
+        "under-window" => Effect::UnderWindowBackground,
+        _ => return Err(format!("Unknown vibrancy material: {material}")),
+    };
+    // The widget is rarely focused, so keep the effect active regardless
+    let effects = EffectsBuilder::new()
+        .effect(effect)
+        .state(EffectState::Active)
+        .build();
+    widget.set_effects(effects).map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn apply_widget_vibrancy(_widget: &tauri::WebviewWindow, _material: Option<&str>) -> Result<(), String> {
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_widget_vibrancy(app: tauri::AppHandle, material: Option<String>) -> Result<(), String> {
+    if let Some(material) = material.as_deref() {
+        if !WIDGET_VIBRANCY_MATERIALS.contains(&material) {
+            return Err(format!(
+                "Unknown vibrancy material: {material} (expected one of {})",
+                WIDGET_VIBRANCY_MATERIALS.join(", ")
+            ));
+        }
+    }
+    if let Some(widget) = app.get_webview_window("widget") {
+        apply_widget_vibrancy(&widget, material.as_deref())?;
+    }
+    let mut settings = load_settings();
+    settings.widget_vibrancy = material;
+    save_settings(&settings)
+}
+
+// ─── Widget Idle Dim ───────────────────────────────────────────────────────
+
+/// Fraction of the active opacity used while the widget is idle.
+const WIDGET_IDLE_OPACITY_FACTOR: f64 = 0.4;
+const IDLE_DIM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Epoch-millis of the last widget interaction (focus, hover).
+static LAST_WIDGET_ACTIVITY: AtomicU64 = AtomicU64::new(0);
+pub(crate) static WIDGET_DIMMED: AtomicBool = AtomicBool::new(false);
+
+/// Whether `set_window_alpha` does anything here. Windows is left out because
+/// WebView2 stops painting once its host window is made layered, which is the
+/// only way to give a top-level HWND an alpha.
+pub(crate) const WINDOW_ALPHA_SUPPORTED: bool = cfg!(any(target_os = "macos", target_os = "linux"));
+
+/// Settings that only take effect where `WINDOW_ALPHA_SUPPORTED`.
+pub(crate) const WINDOW_ALPHA_SETTINGS: [&str; 3] = ["widget_opacity", "widget_idle_dim", "widget_idle_timeout_ms"];
+
+/// Set the native window opacity. Tauri has no cross-platform API for this,
+/// so it goes through NSWindow on macOS and the GTK window on Linux.
+#[cfg(target_os = "macos")]
+pub(crate) fn set_window_alpha(window: &tauri::WebviewWindow, alpha: f64) {
+    use objc2::{msg_send, runtime::AnyObject};
+    let Ok(ns_window) = window.ns_window() else {
+        return;
+    };
+    let ns_window = ns_window as usize;
+    let alpha = alpha.clamp(0.0, 1.0);
+    let _ = window.run_on_main_thread(move || unsafe {
+        let ns_window = &*(ns_window as *mut AnyObject);
+        let _: () = msg_send![ns_window, setAlphaValue: alpha];
+    });
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn set_window_alpha(window: &tauri::WebviewWindow, alpha: f64) {
+    use gtk::prelude::WidgetExt;
+    let target = window.clone();
+    let alpha = alpha.clamp(0.0, 1.0);
+    let _ = window.run_on_main_thread(move || {
+        if let Ok(gtk_window) = target.gtk_window() {
+            gtk_window.set_opacity(alpha);
+        }
+    });
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub(crate) fn set_window_alpha(_window: &tauri::WebviewWindow, _alpha: f64) {}
+
+pub(crate) fn note_widget_activity() {
+    LAST_WIDGET_ACTIVITY.store(now_millis(), Ordering::SeqCst);
+}
+
+fn set_widget_dimmed(app: &tauri::AppHandle, dimmed: bool, active_opacity: f64) {
+    if WIDGET_DIMMED.swap(dimmed, Ordering::SeqCst) == dimmed {
+        return;
+    }
+    if let Some(widget) = app.get_webview_window("widget") {
+        let alpha = if dimmed {
+            active_opacity * WIDGET_IDLE_OPACITY_FACTOR
+        } else {
+            active_opacity
+        };
+        set_window_alpha(&widget, alpha);
+    }
+}
+
+pub(crate) fn run_idle_dim_loop(app: tauri::AppHandle) {
+    while sleep_unless_shutdown(IDLE_DIM_POLL_INTERVAL) {
+        let settings = cached_settings();
+        if !settings.widget_idle_dim {
+            set_widget_dimmed(&app, false, settings.widget_opacity);
+            continue;
+        }
+        let idle_ms = now_millis().saturating_sub(LAST_WIDGET_ACTIVITY.load(Ordering::SeqCst));
+        if idle_ms >= settings.widget_idle_timeout_ms && !is_widget_dragging() {
+            set_widget_dimmed(&app, true, settings.widget_opacity);
+        }
+    }
+}
+
+/// Time since the last keyboard/mouse input anywhere on the system, or
+/// `None` where the platform offers no API for it.
+#[cfg(target_os = "macos")]
+fn system_idle_ms() -> Option<u64> {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state_id: i32, event_type: u32) -> f64;
+    }
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const ANY_INPUT_EVENT: u32 = u32::MAX;
+    let seconds =
+        unsafe { CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT) };
+    // `as` saturates, so computing this before the check is harmless
+    let ms = (seconds * 1000.0) as u64;
+    (seconds.is_finite() && seconds >= 0.0).then_some(ms)
+}
+
+#[cfg(target_os = "windows")]
+fn system_idle_ms() -> Option<u64> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+    let mut info = LastInputInfo {
+        cb_size: std::mem::size_of::<LastInputInfo>() as u32,
+        dw_time: 0,
+    };
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Both are 32-bit tick counts, so wrapping_sub survives the 49-day rollover
+    Some(unsafe { GetTickCount() }.wrapping_sub(info.dw_time) as u64)
+}
+
+/// Asks the XScreenSaver extension, loaded at runtime so a missing libXss
+/// only disables this. Pure Wayland sessions without XWayland get `None`.
+#[cfg(target_os = "linux")]
+#[allow(clippy::unnecessary_cast)] // `c_ulong` is only 32 bits on 32-bit targets
+fn system_idle_ms() -> Option<u64> {
+    use x11_dl::{xlib::Xlib, xss::Xss};
+    let xlib = Xlib::open().ok()?;
+    let xss = Xss::open().ok()?;
+    unsafe {
+        let display = (xlib.XOpenDisplay)(std::ptr::null());
+        if display.is_null() {
+            return None;
+        }
+        let info = (xss.XScreenSaverAllocInfo)();
+        let mut idle = None;
+        if !info.is_null() {
+            let root = (xlib.XDefaultRootWindow)(display);
+            if (xss.XScreenSaverQueryInfo)(display, root, info) != 0 {
+                idle = Some((*info).idle as u64);
+            }
+            (xlib.XFree)(info.cast());
+        }
+        (xlib.XCloseDisplay)(display);
+        idle
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn system_idle_ms() -> Option<u64> {
+    None
+}
+
+/// System-wide input idle time in milliseconds; 0 where unsupported.
+#[tauri::command]
+pub(crate) fn get_idle_time_ms() -> u64 {
+    system_idle_ms().unwrap_or(0)
+}
+
+/// Called by the widget on hover/mouse activity; brightens it if dimmed.
+#[tauri::command]
+pub(crate) fn widget_activity(app: tauri::AppHandle) {
+    note_widget_activity();
+    set_widget_dimmed(&app, false, cached_settings().widget_opacity);
+}
+
+/// Toggle the widget's window shadow; ignored where the platform has none
+/// to toggle.
+#[tauri::command]
+pub(crate) fn set_widget_shadow(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.widget_shadow = enabled;
+    save_settings(&settings)?;
+    if let Some(widget) = app.get_webview_window("widget") {
+        widget.set_shadow(enabled).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn set_widget_opacity(app: tauri::AppHandle, opacity: f64) -> Result<(), String> {
+    if !WINDOW_ALPHA_SUPPORTED {
+        return Err("Widget opacity isn't supported on this platform".into());
+    }
+    if !(0.1..=1.0).contains(&opacity) {
+        return Err("Opacity must be between 0.1 and 1.0".into());
+    }
+    let mut settings = load_settings();
+    settings.widget_opacity = opacity;
+    save_settings(&settings)?;
+    note_widget_activity();
+    WIDGET_DIMMED.store(false, Ordering::SeqCst);
+    if let Some(widget) = app.get_webview_window("widget") {
+        set_window_alpha(&widget, opacity);
+    }
+    Ok(())
+}
+
+// ─── Widget Screenshot ─────────────────────────────────────────────────────
+
+/// CGWindowID of a window, which `screencapture -l` takes.
+#[cfg(target_os = "macos")]
+fn window_number(window: &tauri::WebviewWindow) -> Result<isize, String> {
+    use objc2::{msg_send, runtime::AnyObject};
+    let ns_window = window.ns_window().map_err(|e| e.to_string())? as usize;
+    let (tx, rx) = std::sync::mpsc::channel();
+    window
+        .run_on_main_thread(move || unsafe {
+            let ns_window = &*(ns_window as *mut AnyObject);
+            let number: isize = msg_send![ns_window, windowNumber];
+            let _ = tx.send(number);
+        })
+        .map_err(|e| e.to_string())?;
+    rx.recv_timeout(Duration::from_secs(2)).map_err(|e| e.to_string())
+}
+
+/// Save the widget window, as currently drawn, to a PNG at `path`. Fails if
+/// the widget is hidden or capture isn't supported on this platform.
+#[tauri::command]
+pub(crate) async fn capture_widget_screenshot(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    let widget = app.get_webview_window("widget").ok_or("Widget window not found")?;
+    if !widget.is_visible().unwrap_or(false) {
+        return Err("Widget is hidden".into());
+    }
+    capture_window_png(&widget, Path::new(&path))
+}
+
+#[cfg(target_os = "macos")]
+fn capture_window_png(window: &tauri::WebviewWindow, path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let id = window_number(window)?;
+    // -o drops the window shadow, -x the shutter sound
+    let output = std::process::Command::new("/usr/sbin/screencapture")
+        .args(["-l", &id.to_string(), "-o", "-x", "-t", "png"])
+        .arg(path)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() || !path.exists() {
+        // Usually a missing Screen Recording permission
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Window capture failed: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn capture_window_png(_window: &tauri::WebviewWindow, _path: &Path) -> Result<(), String> {
+    Err("Widget screenshots are only supported on macOS".into())
+}
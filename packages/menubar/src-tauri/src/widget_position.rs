@@ -0,0 +1,639 @@
+//! Saving and restoring the widget position.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::background::{sleep_unless_shutdown, spawn_background};
+use crate::layouts::{clamp_to_area, WindowLayout};
+use crate::notifications::notify;
+use crate::paths::{position_file, position_history_file, write_atomic};
+use crate::settings::{cached_settings, load_settings, save_settings, WidgetSettings};
+use crate::widget::note_widget_activity;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub(crate) struct WidgetPosition {
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+}
+
+/// Widget placement as a share (0–100) of the room the work area leaves it.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub(crate) struct WidgetPositionPct {
+    x_pct: f64,
+    y_pct: f64,
+}
+
+// ─── Widget Position ───────────────────────────────────────────────────────
+
+/// Drags report positions in bursts; only the last one within this window
+/// is written.
+const POSITION_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Latest position not yet written to disk, tagged with the generation it
+/// was saved under so a flush only takes what it actually wrote.
+static PENDING_WIDGET_POSITION: Mutex<Option<(u64, WidgetPosition)>> = Mutex::new(None);
+static POSITION_GENERATION: AtomicU64 = AtomicU64::new(0);
+static POSITION_SAVE_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// Drop a pending debounced save. For explicit placements (anchor, layout,
+/// undo), which must not be overwritten by a stale drag.
+pub(crate) fn discard_pending_position() {
+    if let Ok(mut pending) = PENDING_WIDGET_POSITION.lock() {
+        *pending = None;
+    }
+}
+
+/// Write `position` now; the position it replaces on disk goes onto the undo
+/// history. Leaves any pending debounced save alone: only the flush that
+/// wrote it may take it.
+pub(crate) fn write_widget_position(position: &WidgetPosition) -> Result<(), String> {
+    if let Some(previous) = load_stored_widget_position().filter(|p| p != position) {
+        push_position_history(previous)?;
+    }
+    store_widget_position(position)
+}
+
+fn store_widget_position(position: &WidgetPosition) -> Result<(), String> {
+    let path = position_file().ok_or("Cannot resolve home directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(position).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn load_position_history() -> Vec<WidgetPosition> {
+    position_history_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_position_history(history: &[WidgetPosition]) -> Result<(), String> {
+    let path = position_history_file().ok_or("Cannot resolve home directory")?;
+    let json = serde_json::to_string(history).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Append to the history, dropping the oldest past `widget_position_history`.
+pub(crate) fn push_position_history(position: WidgetPosition) -> Result<(), String> {
+    let depth = cached_settings().widget_position_history;
+    let mut history = load_position_history();
+    history.push(position);
+    let excess = history.len().saturating_sub(depth);
+    history.drain(..excess);
+    save_position_history(&history)
+}
+
+pub(crate) const MAX_POSITION_HISTORY: usize = 50;
+
+/// Keep the last `depth` positions (0 turns undo off), trimming the history
+/// now if it is longer.
+#[tauri::command]
+pub(crate) fn set_widget_position_history(depth: usize) -> Result<(), String> {
+    if depth > MAX_POSITION_HISTORY {
+        return Err(format!("History depth must be at most {MAX_POSITION_HISTORY}"));
+    }
+    let mut settings = load_settings();
+    settings.widget_position_history = depth;
+    save_settings(&settings)?;
+    let mut history = load_position_history();
+    if history.len() > depth {
+        let excess = history.len() - depth;
+        history.drain(..excess);
+        save_position_history(&history)?;
+    }
+    Ok(())
+}
+
+/// Move the widget back to where it was before the last saved move, e.g.
+/// after flinging it off-screen. Returns the restored position, or `None`
+/// when the history is empty. Like a drag, this releases any anchor.
+#[tauri::command]
+pub(crate) fn undo_widget_position(app: tauri::AppHandle) -> Result<Option<WidgetPosition>, String> {
+    discard_pending_position();
+    let mut history = load_position_history();
+    let Some(previous) = history.pop() else {
+        return Ok(None);
+    };
+    save_position_history(&history)?;
+    // Written directly: an undo must not push onto the history it pops from
+    store_widget_position(&previous)?;
+
+    let mut settings = load_settings();
+    let had_anchor = settings.widget_anchor.take().is_some();
+    if settings.widget_position_pct.take().is_some() || had_anchor {
+        save_settings(&settings)?;
+    }
+    if let Some(widget) = app.get_webview_window("widget") {
+        widget
+            .set_position(tauri::PhysicalPosition::new(previous.x as i32, previous.y as i32))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(Some(previous))
+}
+
+/// Write the pending position, then clear it only if no newer save arrived
+/// while writing, so that one is flushed next rather than lost.
+fn flush_widget_position() -> Result<(), String> {
+    let pending = PENDING_WIDGET_POSITION
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let Some((generation, position)) = pending else {
+        return Ok(());
+    };
+    write_widget_position(&position)?;
+    let mut pending = PENDING_WIDGET_POSITION.lock().map_err(|e| e.to_string())?;
+    if pending.as_ref().is_some_and(|(current, _)| *current == generation) {
+        *pending = None;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn save_widget_position(x: f64, y: f64) -> Result<(), String> {
+    let position = WidgetPosition { x, y };
+    // A position other than the one the anchor resolved to means the user
+    // dragged the widget, which releases the anchor (or percentage).
+    let mut settings = load_settings();
+    let placed = settings.widget_anchor.is_some() || settings.widget_position_pct.is_some();
+    if placed && load_widget_position().as_ref() != Some(&position) {
+        settings.widget_anchor = None;
+        settings.widget_position_pct = None;
+        save_settings(&settings)?;
+    }
+    let generation = POSITION_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    *PENDING_WIDGET_POSITION.lock().map_err(|e| e.to_string())? = Some((generation, position));
+    // Mid-drag positions are only kept in memory; end_widget_drag writes the last one
+    if is_widget_dragging() {
+        return Ok(());
+    }
+    if !POSITION_SAVE_SCHEDULED.swap(true, Ordering::SeqCst) {
+        spawn_background("position-save", || {
+            sleep_unless_shutdown(POSITION_SAVE_DELAY);
+            POSITION_SAVE_SCHEDULED.store(false, Ordering::SeqCst);
+            if !is_widget_dragging() {
+                let _ = flush_widget_position();
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Set between `begin_widget_drag` and `end_widget_drag`. While set, position
+/// saves are held until the drop and focus-driven widget behavior is skipped.
+static WIDGET_DRAGGING: AtomicBool = AtomicBool::new(false);
+/// Bumped per drag, so a stale safety timer can't end a newer drag.
+static WIDGET_DRAG_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// A drag whose end was never reported (e.g. the webview reloaded mid-drag)
+/// is ended after this long.
+const WIDGET_DRAG_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub(crate) fn is_widget_dragging() -> bool {
+    WIDGET_DRAGGING.load(Ordering::SeqCst)
+}
+
+#[tauri::command]
+pub(crate) fn begin_widget_drag() {
+    WIDGET_DRAGGING.store(true, Ordering::SeqCst);
+    let generation = WIDGET_DRAG_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    spawn_background("widget-drag-timeout", move || {
+        if sleep_unless_shutdown(WIDGET_DRAG_TIMEOUT)
+            && WIDGET_DRAG_GENERATION.load(Ordering::SeqCst) == generation
+        {
+            let _ = end_widget_drag();
+        }
+    });
+}
+
+/// Finish a drag and write the final position right away.
+#[tauri::command]
+pub(crate) fn end_widget_drag() -> Result<(), String> {
+    if !WIDGET_DRAGGING.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    note_widget_activity();
+    flush_widget_position()
+}
+
+/// Write anything still held back by debouncing. Called on every quit path
+/// so the user's last change survives.
+#[tauri::command]
+pub(crate) fn flush_pending_writes() -> Result<(), String> {
+    flush_widget_position()
+}
+
+// ─── Widget Anchors ────────────────────────────────────────────────────────
+
+pub(crate) const WIDGET_ANCHORS: [&str; 6] = [
+    "top-left",
+    "top-center",
+    "top-right",
+    "bottom-left",
+    "bottom-center",
+    "bottom-right",
+];
+/// Gap between the widget and the work-area edge, in logical pixels.
+const WIDGET_ANCHOR_MARGIN: f64 = 16.0;
+
+/// Set once the pinned monitor was reported missing, so the notice isn't
+/// repeated on every show; cleared when it reappears.
+static WIDGET_MONITOR_MISSING: AtomicBool = AtomicBool::new(false);
+
+/// The monitor the widget is pinned to by `widget_monitor`, or the primary
+/// monitor (with a one-time notice) if that one isn't connected. `None` when
+/// no pin is set.
+pub(crate) fn widget_pinned_monitor(widget: &tauri::WebviewWindow) -> Option<tauri::Monitor> {
+    let name = cached_settings().widget_monitor?;
+    let monitors = widget.available_monitors().unwrap_or_default();
+    if let Some(monitor) = monitors.into_iter().find(|m| m.name() == Some(&name)) {
+        WIDGET_MONITOR_MISSING.store(false, Ordering::SeqCst);
+        return Some(monitor);
+    }
+    if !WIDGET_MONITOR_MISSING.swap(true, Ordering::SeqCst) {
+        let _ = notify(
+            widget.app_handle(),
+            "Hexdeck widget display not found",
+            &format!("\"{name}\" isn't connected; showing the widget on the primary display."),
+        );
+    }
+    widget.primary_monitor().ok().flatten()
+}
+
+/// Keep the widget inside its pinned monitor's work area, if it is pinned.
+pub(crate) fn pin_widget_to_monitor(widget: &tauri::WebviewWindow) {
+    let Some(monitor) = widget_pinned_monitor(widget) else {
+        return;
+    };
+    let (Ok(position), Ok(size)) = (widget.outer_position(), widget.outer_size()) else {
+        return;
+    };
+    let area = widget_work_area(widget, &monitor);
+    let layout = WindowLayout {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        visible: true,
+    };
+    let clamped = clamp_to_area(layout, area.position, area.size);
+    if (clamped.x, clamped.y) != (position.x, position.y) {
+        let _ = widget.set_position(tauri::PhysicalPosition::new(clamped.x, clamped.y));
+        discard_pending_position();
+        let _ = write_widget_position(&WidgetPosition {
+            x: clamped.x as f64,
+            y: clamped.y as f64,
+        });
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct MonitorInfo {
+    pub(crate) name: Option<String>,
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) scale_factor: f64,
+    primary: bool,
+}
+
+/// Connected monitors in physical pixels; names are what `widget_monitor`
+/// matches against.
+#[tauri::command]
+pub(crate) fn list_monitors(app: tauri::AppHandle) -> Vec<MonitorInfo> {
+    let primary = app.primary_monitor().ok().flatten();
+    let is_primary = |m: &tauri::Monitor| {
+        primary
+            .as_ref()
+            .is_some_and(|p| p.name() == m.name() && p.position() == m.position())
+    };
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| MonitorInfo {
+            name: m.name().cloned(),
+            x: m.position().x,
+            y: m.position().y,
+            width: m.size().width,
+            height: m.size().height,
+            scale_factor: m.scale_factor(),
+            primary: is_primary(m),
+        })
+        .collect()
+}
+
+/// Scale factor of each existing window, keyed by label ("main", "widget").
+#[tauri::command]
+pub(crate) fn get_window_scale_factors(app: tauri::AppHandle) -> BTreeMap<String, f64> {
+    ["main", "widget"]
+        .into_iter()
+        .filter_map(|label| {
+            let scale = app.get_webview_window(label)?.scale_factor().ok()?;
+            Some((label.to_string(), scale))
+        })
+        .collect()
+}
+
+/// Pin the widget to the monitor called `name`, or unpin with `None`.
+#[tauri::command]
+pub(crate) fn set_widget_monitor(app: tauri::AppHandle, name: Option<String>) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.widget_monitor = name;
+    save_settings(&settings)?;
+    WIDGET_MONITOR_MISSING.store(false, Ordering::SeqCst);
+    if let Some(widget) = app.get_webview_window("widget") {
+        match apply_widget_placement(&app, &settings) {
+            Some(result) => result?,
+            None => pin_widget_to_monitor(&widget),
+        }
+    }
+    Ok(())
+}
+
+/// Region of a monitor the widget is placed within (physical pixels).
+pub(crate) struct WorkArea {
+    pub(crate) position: tauri::PhysicalPosition<i32>,
+    pub(crate) size: tauri::PhysicalSize<u32>,
+}
+
+/// The monitor's work area, with its top pushed below the notch/menu bar
+/// safe area when `respect_safe_area` is on. The work area alone excludes the
+/// menu bar only while it is shown; with it auto-hidden, the top row of a
+/// notched display runs under the camera housing.
+pub(crate) fn widget_work_area(widget: &tauri::WebviewWindow, monitor: &tauri::Monitor) -> WorkArea {
+    let work = monitor.work_area();
+    let mut area = WorkArea {
+        position: work.position,
+        size: work.size,
+    };
+    if !cached_settings().respect_safe_area {
+        return area;
+    }
+    let inset = screen_top_safe_inset(widget, monitor).unwrap_or(0.0);
+    let safe_top = monitor.position().y + (inset * monitor.scale_factor()).round() as i32;
+    if safe_top > area.position.y {
+        let shift = (safe_top - area.position.y) as u32;
+        area.position.y = safe_top;
+        area.size.height = area.size.height.saturating_sub(shift);
+    }
+    area
+}
+
+/// `NSScreen.safeAreaInsets.top` (points) of the screen matching `monitor`
+/// by name; non-zero only on notched displays (macOS 12+). Placement runs
+/// on background threads too, so the AppKit query goes through `window`'s
+/// main thread, as in `window_number`.
+#[cfg(target_os = "macos")]
+fn screen_top_safe_inset(window: &tauri::WebviewWindow, monitor: &tauri::Monitor) -> Option<f64> {
+    use objc2::encode::{Encode, Encoding};
+    use objc2::{class, msg_send, runtime::AnyObject, sel};
+
+    #[repr(C)]
+    struct NSEdgeInsets {
+        top: f64,
+        left: f64,
+        bottom: f64,
+        right: f64,
+    }
+    unsafe impl Encode for NSEdgeInsets {
+        const ENCODING: Encoding = Encoding::Struct(
+            "NSEdgeInsets",
+            &[Encoding::Double, Encoding::Double, Encoding::Double, Encoding::Double],
+        );
+    }
+
+    let name = monitor.name()?.clone();
+    let lookup = move || unsafe {
+        let screens: *mut AnyObject = msg_send![class!(NSScreen), screens];
+        if screens.is_null() {
+            return None;
+        }
+        let count: usize = msg_send![&*screens, count];
+        for i in 0..count {
+            let screen: *mut AnyObject = msg_send![&*screens, objectAtIndex: i];
+            let localized: *mut AnyObject = msg_send![&*screen, localizedName];
+            if localized.is_null() {
+                continue;
+            }
+            let utf8: *const std::ffi::c_char = msg_send![&*localized, UTF8String];
+            if utf8.is_null() || std::ffi::CStr::from_ptr(utf8).to_string_lossy() != name.as_str() {
+                continue;
+            }
+            let supported: bool = msg_send![&*screen, respondsToSelector: sel!(safeAreaInsets)];
+            if !supported {
+                return None;
+            }
+            let insets: NSEdgeInsets = msg_send![&*screen, safeAreaInsets];
+            return Some(insets.top);
+        }
+        None
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    window
+        .run_on_main_thread(move || {
+            let _ = tx.send(lookup());
+        })
+        .ok()?;
+    rx.recv_timeout(Duration::from_secs(2)).ok().flatten()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn screen_top_safe_inset(_window: &tauri::WebviewWindow, _monitor: &tauri::Monitor) -> Option<f64> {
+    None
+}
+
+#[tauri::command]
+pub(crate) fn set_respect_safe_area(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.respect_safe_area = enabled;
+    save_settings(&settings)?;
+    if apply_widget_placement(&app, &settings).is_none() {
+        if let Some(widget) = app.get_webview_window("widget") {
+            pin_widget_to_monitor(&widget);
+        }
+    }
+    Ok(())
+}
+
+/// Monitor placements resolve against: the pinned one, else the one the
+/// widget is currently on, falling back to the primary monitor.
+fn widget_placement_monitor(widget: &tauri::WebviewWindow) -> Result<tauri::Monitor, String> {
+    let current = match widget_pinned_monitor(widget) {
+        Some(monitor) => Some(monitor),
+        None => widget.current_monitor().map_err(|e| e.to_string())?,
+    };
+    match current {
+        Some(monitor) => Ok(monitor),
+        None => widget
+            .primary_monitor()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "No monitor available".to_string()),
+    }
+}
+
+/// Physical position of the widget at `anchor` within the work area of its
+/// placement monitor.
+fn resolve_widget_anchor(
+    widget: &tauri::WebviewWindow,
+    anchor: &str,
+) -> Result<tauri::PhysicalPosition<i32>, String> {
+    let monitor = widget_placement_monitor(widget)?;
+    let area = widget_work_area(widget, &monitor);
+    let size = widget.outer_size().map_err(|e| e.to_string())?;
+    let margin = (WIDGET_ANCHOR_MARGIN * monitor.scale_factor()).round() as i32;
+
+    let (width, height) = (size.width as i32, size.height as i32);
+    let (area_width, area_height) = (area.size.width as i32, area.size.height as i32);
+    let left = area.position.x + margin;
+    let center = area.position.x + (area_width - width) / 2;
+    let right = area.position.x + area_width - width - margin;
+    let top = area.position.y + margin;
+    let bottom = area.position.y + area_height - height - margin;
+
+    let (x, y) = match anchor {
+        "top-left" => (left, top),
+        "top-center" => (center, top),
+        "top-right" => (right, top),
+        "bottom-left" => (left, bottom),
+        "bottom-center" => (center, bottom),
+        "bottom-right" => (right, bottom),
+        _ => return Err(format!("Unknown anchor: {anchor}")),
+    };
+    Ok(tauri::PhysicalPosition { x, y })
+}
+
+/// Physical position for a percentage placement. Percentages span the room
+/// left beside the widget, so 0 and 100 put it flush with either edge.
+fn resolve_widget_position_pct(
+    widget: &tauri::WebviewWindow,
+    pct: WidgetPositionPct,
+) -> Result<tauri::PhysicalPosition<i32>, String> {
+    let area = widget_work_area(widget, &widget_placement_monitor(widget)?);
+    let size = widget.outer_size().map_err(|e| e.to_string())?;
+    let room_x = (area.size.width as f64 - size.width as f64).max(0.0);
+    let room_y = (area.size.height as f64 - size.height as f64).max(0.0);
+    Ok(tauri::PhysicalPosition {
+        x: area.position.x + (room_x * pct.x_pct / 100.0).round() as i32,
+        y: area.position.y + (room_y * pct.y_pct / 100.0).round() as i32,
+    })
+}
+
+fn apply_widget_anchor(app: &tauri::AppHandle, anchor: &str) -> Result<(), String> {
+    let widget = app
+        .get_webview_window("widget")
+        .ok_or("Widget window not found")?;
+    let position = resolve_widget_anchor(&widget, anchor)?;
+    move_widget_to(&widget, position)
+}
+
+fn apply_widget_position_pct(app: &tauri::AppHandle, pct: WidgetPositionPct) -> Result<(), String> {
+    let widget = app
+        .get_webview_window("widget")
+        .ok_or("Widget window not found")?;
+    let position = resolve_widget_position_pct(&widget, pct)?;
+    move_widget_to(&widget, position)
+}
+
+/// Re-resolve the saved anchor or percentage placement; `None` if neither is set.
+fn apply_widget_placement(app: &tauri::AppHandle, settings: &WidgetSettings) -> Option<Result<(), String>> {
+    if let Some(anchor) = &settings.widget_anchor {
+        return Some(apply_widget_anchor(app, anchor));
+    }
+    settings
+        .widget_position_pct
+        .map(|pct| apply_widget_position_pct(app, pct))
+}
+
+/// Move the widget and persist the resolved coordinates.
+fn move_widget_to(widget: &tauri::WebviewWindow, position: tauri::PhysicalPosition<i32>) -> Result<(), String> {
+    widget
+        .set_position(tauri::Position::Physical(position))
+        .map_err(|e| e.to_string())?;
+    discard_pending_position();
+    write_widget_position(&WidgetPosition {
+        x: position.x as f64,
+        y: position.y as f64,
+    })
+}
+
+/// Move the widget to a named corner of the current monitor's work area and
+/// remember the anchor so it follows geometry changes.
+#[tauri::command]
+pub(crate) fn set_widget_anchor(app: tauri::AppHandle, anchor: String) -> Result<(), String> {
+    if !WIDGET_ANCHORS.contains(&anchor.as_str()) {
+        return Err(format!(
+            "Unknown anchor: {anchor} (expected one of {})",
+            WIDGET_ANCHORS.join(", ")
+        ));
+    }
+    apply_widget_anchor(&app, &anchor)?;
+    let mut settings = load_settings();
+    settings.widget_anchor = Some(anchor);
+    settings.widget_position_pct = None;
+    save_settings(&settings)
+}
+
+/// Place the widget at a percentage of the current monitor's work area
+/// (clamped to 0–100) and remember it, like an anchor, so it re-resolves
+/// when the monitor changes.
+#[tauri::command]
+pub(crate) fn set_widget_position_pct(app: tauri::AppHandle, x_pct: f64, y_pct: f64) -> Result<(), String> {
+    if !x_pct.is_finite() || !y_pct.is_finite() {
+        return Err("Percentages must be numbers".into());
+    }
+    let pct = WidgetPositionPct {
+        x_pct: x_pct.clamp(0.0, 100.0),
+        y_pct: y_pct.clamp(0.0, 100.0),
+    };
+    apply_widget_position_pct(&app, pct)?;
+    let mut settings = load_settings();
+    settings.widget_position_pct = Some(pct);
+    settings.widget_anchor = None;
+    save_settings(&settings)
+}
+
+/// Re-apply the saved anchor or percentage placement, if any, or center on
+/// the pinned monitor. Returns whether the widget was placed, so the frontend
+/// can skip its default centering.
+#[tauri::command]
+pub(crate) fn restore_widget_placement(app: tauri::AppHandle) -> bool {
+    if let Some(result) = apply_widget_placement(&app, &cached_settings()) {
+        return result.is_ok();
+    }
+    let Some(widget) = app.get_webview_window("widget") else {
+        return false;
+    };
+    let (Some(monitor), Ok(size)) = (widget_pinned_monitor(&widget), widget.outer_size()) else {
+        return false;
+    };
+    let area = widget_work_area(&widget, &monitor);
+    let x = area.position.x + (area.size.width as i32 - size.width as i32) / 2;
+    let y = area.position.y + (area.size.height as i32 - size.height as i32) / 2;
+    widget
+        .set_position(tauri::PhysicalPosition::new(x, y))
+        .is_ok()
+}
+
+#[tauri::command]
+pub(crate) fn load_widget_position() -> Option<WidgetPosition> {
+    if let Some((_, pending)) = PENDING_WIDGET_POSITION.lock().ok().and_then(|p| p.clone()) {
+        return Some(pending);
+    }
+    load_stored_widget_position()
+}
+
+/// The position on disk, ignoring any pending save.
+fn load_stored_widget_position() -> Option<WidgetPosition> {
+    let path = position_file()?;
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
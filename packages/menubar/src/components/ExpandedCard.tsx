@@ -1,4 +1,3 @@
-import { getCurrentWindow } from "@tauri-apps/api/window";
 import { open } from "@tauri-apps/plugin-shell";
 import { invoke } from "@tauri-apps/api/core";
 import type { TraySeverity, HexcoreAlert } from "../lib/alerts";
@@ -7,6 +6,7 @@ import { AlertList } from "./AlertList";
 import { AgentList } from "./AgentList";
 import { GlowHex } from "./GlowHex";
 import { ColorLegendPopover } from "./ColorLegendPopover";
+import { startWidgetDrag } from "../hooks/useWidgetState";
 
 interface ExpandedCardProps {
   severity: TraySeverity;
@@ -35,7 +35,7 @@ export function ExpandedCard({
       {/* Header — drag region */}
       <div
         className="flex items-center justify-between px-4 py-3 border-b border-dash-border"
-        onMouseDown={(e) => { if (e.button === 0) getCurrentWindow().startDragging(); }}
+        onMouseDown={(e) => { if (e.button === 0) startWidgetDrag(); }}
       >
         <div className="flex items-center gap-2.5">
           <GlowHex severity={severity} size={4} />
@@ -1,7 +1,6 @@
-import { getCurrentWindow } from "@tauri-apps/api/window";
 import type { TraySeverity, HexcoreAlert } from "../lib/alerts";
 import type { DashboardState } from "../lib/types";
-import type { WidgetState } from "../hooks/useWidgetState";
+import { startWidgetDrag, type WidgetState } from "../hooks/useWidgetState";
 import { FaviconIcon } from "./FaviconIcon";
 import { SummaryPill } from "./SummaryPill";
 import { ExpandedCard } from "./ExpandedCard";
@@ -45,7 +44,7 @@ export function FloatingWidget({
     const onMove = (moveE: MouseEvent) => {
       if (Math.abs(moveE.screenX - startX) > 3 || Math.abs(moveE.screenY - startY) > 3) {
         cleanup();
-        getCurrentWindow().startDragging();
+        startWidgetDrag();
       }
     };
 
@@ -1,6 +1,6 @@
 import { useEffect } from "react";
 import { check } from "@tauri-apps/plugin-updater";
-import { relaunch } from "@tauri-apps/plugin-process";
+import { invoke } from "@tauri-apps/api/core";
 
 export function useAutoUpdate() {
   useEffect(() => {
@@ -11,9 +11,13 @@ export function useAutoUpdate() {
         const update = await check();
         if (cancelled || !update) return;
 
+        // Orange tray icon while the update (and server restart) is in flight
+        invoke("update_tray_icon", { color: "orange" }).catch(() => {});
         await update.downloadAndInstall();
         if (!cancelled) {
-          await relaunch();
+          // relaunch_app stops the server and marks the relaunch so the new
+          // instance waits for the old server before spawning its own
+          await invoke("relaunch_app");
         }
       } catch (e) {
         console.warn("Auto-update failed:", e);
@@ -19,6 +19,17 @@ export interface WidgetState {
   collapseToFavicon: () => void;
 }
 
+// Native window drag bracketed by begin/end so the backend holds position
+// saves and ignores focus flicker until the drop.
+export async function startWidgetDrag() {
+  await invoke("begin_widget_drag").catch(() => {});
+  try {
+    await getCurrentWindow().startDragging();
+  } finally {
+    invoke("end_widget_drag").catch(() => {});
+  }
+}
+
 export function useWidgetState(interactionsBlocked = false): WidgetState {
   const [tier, setTier] = useState<WidgetTier>("favicon");
   const collapseTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
@@ -75,6 +86,7 @@ export function useWidgetState(interactionsBlocked = false): WidgetState {
   }, []);
 
   const onHoverEnter = useCallback(() => {
+    invoke("widget_activity").catch(() => {});
     if (interactionsBlocked) return;
     clearCollapseTimer();
   }, [interactionsBlocked, clearCollapseTimer]);
@@ -122,10 +134,11 @@ export function useWidgetState(interactionsBlocked = false): WidgetState {
     return () => unlisten?.();
   }, []);
 
-  // Always center on screen at startup
+  // Center on screen at startup, unless a saved anchor already placed the widget
   useEffect(() => {
     (async () => {
       try {
+        if (await invoke<boolean>("restore_widget_placement")) return;
         const win = getCurrentWindow();
         const scale = window.devicePixelRatio || 1;
         const widgetPhysSize = 48 * scale;
@@ -1,9 +1,9 @@
 import { existsSync, mkdirSync, readFileSync, writeFileSync, readdirSync } from "fs";
 import { join } from "path";
-import { homedir } from "os";
+import { hexdeckDir } from "../core/paths.js";
 import type { Checkpoint } from "../types/index.js";
 
-const HEXDECK_DIR = join(homedir(), ".hexdeck");
+const HEXDECK_DIR = hexdeckDir();
 const CHECKPOINTS_DIR = join(HEXDECK_DIR, "checkpoints");
 
 function ensureDir(dir: string) {
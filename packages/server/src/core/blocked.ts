@@ -1,6 +1,7 @@
 import { existsSync, readFileSync, writeFileSync, mkdirSync, statSync } from "node:fs";
 import { basename, join } from "node:path";
 import { homedir } from "node:os";
+import { hexdeckDir } from "./paths.js";
 import { getActiveSessions } from "../discovery/sessions.js";
 import type { SessionInfo } from "../types/index.js";
 
@@ -333,7 +334,7 @@ export function describeBlockedTool(info: BlockedInfo): string {
 
 const CLAUDE_DIR = join(homedir(), ".claude");
 const SETTINGS_PATH = join(CLAUDE_DIR, "settings.json");
-const HEXDECK_DIR = join(homedir(), ".hexdeck");
+const HEXDECK_DIR = hexdeckDir();
 const HOOKS_DIR = join(HEXDECK_DIR, "hooks");
 
 const HOOK_MARKER = "localhost:7433/api/hooks";
@@ -1,6 +1,7 @@
 import { readFileSync, statSync } from "fs";
 import { join } from "path";
-import { homedir, userInfo } from "os";
+import { userInfo } from "os";
+import { hexdeckDir } from "./paths.js";
 
 // ─── Types ──────────────────────────────────────────────────────────────────
 
@@ -30,7 +31,7 @@ const OPERATOR_COLORS = [
 
 // ─── Config Cache ───────────────────────────────────────────────────────────
 
-const CONFIG_PATH = join(homedir(), ".hexdeck", "operators.json");
+const CONFIG_PATH = join(hexdeckDir(), "operators.json");
 
 let cachedConfig: HexcoreConfig | null = null;
 let cachedMtimeMs = 0;
@@ -1,6 +1,5 @@
 import { statSync, existsSync, readFileSync, writeFileSync, mkdirSync } from "fs";
 import { basename, join } from "path";
-import { homedir } from "os";
 import { getActiveSessions, listProjects, listSessions } from "../discovery/sessions.js";
 import { getActiveCodexSessions, discoverCodexSessions } from "../discovery/codex.js";
 import { parseSessionFile, parseSystemLines } from "../parser/jsonl.js";
@@ -11,6 +10,7 @@ import { getUncommittedFiles } from "./collisions.js";
 import { buildFeed } from "./feed.js";
 import { hasBlockedSession, getBlockedForSession, describeBlockedTool, extractToolDetail, isSessionStopped } from "./blocked.js";
 import { formatIdleDuration } from "./duration.js";
+import { hexdeckDir } from "./paths.js";
 import { computeAgentRisk, computeWorkstreamRisk } from "./risk.js";
 import { resolveCodexBusyIdle } from "./codex-status.js";
 import { loadOperatorConfig, getSelfName, operatorId as makeOperatorId, getOperatorColor } from "./config.js";
@@ -106,7 +106,7 @@ function hashToIndex(id: string): number {
 
 // ─── Persistent label store (survives server restarts) ───────────────────────
 
-const HEXDECK_DIR = join(homedir(), ".hexdeck");
+const HEXDECK_DIR = hexdeckDir();
 const LABELS_PATH = join(HEXDECK_DIR, "labels.json");
 
 interface LabelEntry {
@@ -0,0 +1,25 @@
+import { describe, it, expect } from "vitest";
+import { isLogLevel, shouldLog } from "./log-level.js";
+
+describe("isLogLevel", () => {
+  it("accepts known levels", () => {
+    expect(isLogLevel("debug")).toBe(true);
+  });
+
+  it("rejects unknown values", () => {
+    expect(isLogLevel("verbose")).toBe(false);
+    expect(isLogLevel(undefined)).toBe(false);
+  });
+});
+
+describe("shouldLog", () => {
+  it("logs at and above the threshold's severity", () => {
+    expect(shouldLog("error", "info")).toBe(true);
+    expect(shouldLog("info", "info")).toBe(true);
+  });
+
+  it("drops messages more verbose than the threshold", () => {
+    expect(shouldLog("debug", "info")).toBe(false);
+    expect(shouldLog("info", "error")).toBe(false);
+  });
+});
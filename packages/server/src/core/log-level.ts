@@ -0,0 +1,60 @@
+// ─── Runtime log level ──────────────────────────────────────────────────────
+// The server logs through console.*; this filters those calls by level so
+// verbosity can be set at startup (HEXDECK_LOG_LEVEL) or changed live via
+// /admin/log-level without a restart.
+
+export const LOG_LEVELS = ["error", "warn", "info", "debug", "trace"] as const;
+export type LogLevel = (typeof LOG_LEVELS)[number];
+
+export const LOG_LEVEL_ENV = "HEXDECK_LOG_LEVEL";
+const DEFAULT_LOG_LEVEL: LogLevel = "info";
+
+/** Level each console method logs at. */
+const CONSOLE_LEVELS = {
+  error: "error",
+  warn: "warn",
+  info: "info",
+  log: "info",
+  debug: "debug",
+  trace: "trace",
+} as const satisfies Record<string, LogLevel>;
+
+type ConsoleMethod = keyof typeof CONSOLE_LEVELS;
+
+export function isLogLevel(value: unknown): value is LogLevel {
+  return typeof value === "string" && (LOG_LEVELS as readonly string[]).includes(value);
+}
+
+export function shouldLog(level: LogLevel, threshold: LogLevel): boolean {
+  return LOG_LEVELS.indexOf(level) <= LOG_LEVELS.indexOf(threshold);
+}
+
+let currentLevel: LogLevel = DEFAULT_LOG_LEVEL;
+let installed = false;
+
+export function getLogLevel(): LogLevel {
+  return currentLevel;
+}
+
+export function setLogLevel(level: LogLevel): void {
+  currentLevel = level;
+}
+
+/**
+ * Wrap the console methods so calls below the current level are dropped.
+ * Reads the initial level from HEXDECK_LOG_LEVEL; safe to call more than once.
+ */
+export function installLogLevelFilter(): void {
+  const fromEnv = process.env[LOG_LEVEL_ENV];
+  if (isLogLevel(fromEnv)) currentLevel = fromEnv;
+  if (installed) return;
+  installed = true;
+
+  for (const method of Object.keys(CONSOLE_LEVELS) as ConsoleMethod[]) {
+    const original = console[method].bind(console);
+    const level = CONSOLE_LEVELS[method];
+    console[method] = (...args: unknown[]) => {
+      if (shouldLog(level, currentLevel)) original(...args);
+    };
+  }
+}
@@ -0,0 +1,14 @@
+import { join } from "node:path";
+import { homedir } from "node:os";
+
+// ─── State directory ────────────────────────────────────────────────────────
+// Everything the server persists lives under one directory. The menubar
+// points HEXDECK_HOME at its (possibly relocated) state directory, and it
+// also lets containers/CI run without a home directory.
+
+export const HEXDECK_HOME_ENV = "HEXDECK_HOME";
+
+/** Base directory for server state: $HEXDECK_HOME, else ~/.hexdeck. */
+export function hexdeckDir(): string {
+  return process.env[HEXDECK_HOME_ENV] || join(homedir(), ".hexdeck");
+}
@@ -1,13 +1,13 @@
 import { readFileSync, writeFileSync, statSync, mkdirSync, chmodSync } from "fs";
 import { join, dirname } from "path";
-import { homedir } from "os";
+import { hexdeckDir } from "../core/paths.js";
 import crypto from "crypto";
 import type { RelayConfig, RelayTarget } from "./types.js";
 
 // ─── Config Cache ───────────────────────────────────────────────────────────
 
-const CONFIG_PATH = join(homedir(), ".hexdeck", "relay.json");
-const KEY_PATH = join(homedir(), ".hexdeck", "relay.key");
+const CONFIG_PATH = join(hexdeckDir(), "relay.json");
+const KEY_PATH = join(hexdeckDir(), "relay.key");
 
 let cachedConfig: RelayConfig | null = null;
 let cachedMtimeMs = 0;
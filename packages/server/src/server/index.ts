@@ -11,6 +11,7 @@ import { blockedSessions, clearBlockedSession, clearStaleBlocked, ensureHooks, c
 import { relayManager } from "../relay/manager.js";
 import { parseConnectLink, exchangeConnectLink, createRelayClaim, deriveHttpBaseFromWs } from "../relay/link.js";
 import { storeClaim, getClaim, removeClaim, cleanupExpiredClaims } from "../relay/claims.js";
+import { LOG_LEVELS, getLogLevel, installLogLevelFilter, isLogLevel, setLogLevel } from "../core/log-level.js";
 
 // ─── Types ───────────────────────────────────────────────────────────────────
 
@@ -552,6 +553,24 @@ export function createApp(options?: { dashboardDir?: string }): Hono {
   /** Health check */
   app.get("/api/health", (c) => c.json({ status: "ok" }));
 
+  /** Current log level */
+  app.get("/admin/log-level", (c) => c.json({ level: getLogLevel() }));
+
+  /** Change the log level live */
+  app.post("/admin/log-level", async (c) => {
+    let body: Record<string, unknown>;
+    try {
+      body = await c.req.json<Record<string, unknown>>();
+    } catch {
+      return c.json({ error: "Invalid JSON body" }, 400);
+    }
+    if (!isLogLevel(body.level)) {
+      return c.json({ error: `level must be one of ${LOG_LEVELS.join(", ")}` }, 400);
+    }
+    setLogLevel(body.level);
+    return c.json({ level: body.level });
+  });
+
   // ─── Static Dashboard Serving ─────────────────────────────────────────────
 
   const dashboardDir = options?.dashboardDir;
@@ -574,6 +593,7 @@ export function createApp(options?: { dashboardDir?: string }): Hono {
 
 export function startServer(options?: StartServerOptions): ServerType {
   const port = options?.port ?? parseInt(process.env.PORT ?? "7433", 10);
+  installLogLevelFilter();
   const app = createApp({ dashboardDir: options?.dashboardDir });
 
   const server = serve({ fetch: app.fetch, port }, (info) => {
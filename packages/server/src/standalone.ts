@@ -1,13 +1,13 @@
 /**
  * Bun-compilable standalone entry point for the Hexdeck server.
  * Parses --port and --dashboard-dir from argv, writes a PID file,
- * redirects logs to ~/.hexdeck/server.log, and cleans up on exit.
+ * redirects logs to server.log in the state directory, and cleans up on exit.
  */
 import { startServer } from "./server/index.js";
 import { removeHooks } from "./core/blocked.js";
 import { existsSync, mkdirSync, writeFileSync, unlinkSync, createWriteStream } from "node:fs";
 import { join } from "node:path";
-import { homedir } from "node:os";
+import { hexdeckDir } from "./core/paths.js";
 
 const args = process.argv.slice(2);
 
@@ -22,11 +22,11 @@ function getArg(name: string): string | undefined {
 const port = parseInt(getArg("--port") ?? "7433", 10);
 const dashboardDir = getArg("--dashboard-dir") ?? undefined;
 
-const HEXDECK_DIR = join(homedir(), ".hexdeck");
+const HEXDECK_DIR = hexdeckDir();
 const PID_FILE = join(HEXDECK_DIR, "server.pid");
 const LOG_FILE = join(HEXDECK_DIR, "server.log");
 
-// Ensure ~/.hexdeck exists
+// Ensure the state directory exists
 if (!existsSync(HEXDECK_DIR)) {
   mkdirSync(HEXDECK_DIR, { recursive: true });
 }